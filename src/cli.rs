@@ -0,0 +1,521 @@
+use chrono_tz::Tz;
+use clap::{CommandFactory, Parser, Subcommand};
+
+use crate::due_date::{parse_timezone, DueDate};
+use crate::lateness::LatenessPolicy;
+use crate::report::ReportFormat;
+use crate::sections::parse_section_due;
+use crate::TimestampSource;
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Calculates late day usage for student repos in a GitLab group"
+)]
+pub struct Cli {
+    /// GitLab host to talk to [default: git.uwaterloo.ca, or whatever the config file says]
+    #[arg(long, global = true, env = "LATE_DAYS_GITLAB_HOST")]
+    pub host: Option<String>,
+
+    /// Use plain HTTP instead of HTTPS; only for local test instances
+    #[arg(long, global = true, env = "LATE_DAYS_GITLAB_INSECURE")]
+    pub insecure: bool,
+
+    /// Config file to read settings from [default: late-days.toml, if it exists]
+    #[arg(long, global = true, env = "LATE_DAYS_CONFIG")]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// Parsed once at startup, so the size difference between the variants doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Calculate late days for one assessment and write the CSV reports
+    ///
+    /// Anything not given on the command line is read from the assessment's entry in the
+    /// config file, e.g. `run a2`.
+    Run(RunArgs),
+
+    /// Add up the late days in the ledger and show what each student has left
+    Balance(BalanceArgs),
+}
+
+#[derive(Debug, Default, clap::Args)]
+pub struct RunArgs {
+    /// Assessment to run, as listed in the config file
+    #[arg(value_name = "DESIGNATION", conflicts_with = "designation")]
+    pub assessment: Option<String>,
+
+    /// How the repos are designated, e.g. a1, p or final
+    #[arg(long)]
+    pub designation: Option<String>,
+
+    /// Full hash of the last starter code commit; comma-separate several if it was updated
+    #[arg(long, value_delimiter = ',')]
+    pub starter_commit: Vec<String>,
+
+    /// GitLab group the repos live in, e.g. ece459-1231
+    #[arg(long)]
+    pub group: Option<String>,
+
+    /// Due date and time, e.g. "2023-01-27 23:59" in the course timezone, or RFC 3339 with an
+    /// offset such as 2023-01-27T23:59:00-05:00
+    #[arg(long)]
+    pub due: Option<DueDate>,
+
+    /// IANA timezone that due dates are written in, e.g. America/Vancouver
+    /// [default: Canada/Eastern]
+    #[arg(long, value_parser = parse_timezone)]
+    pub timezone: Option<Tz>,
+
+    /// How many minutes late a submission can be before it counts as late [default: 0]
+    #[arg(long)]
+    pub tolerance: Option<u64>,
+
+    /// CSV file of students and groups, one repo per line
+    #[arg(long)]
+    pub roster: Option<String>,
+
+    /// The roster starts with a header row naming its username, group and repo columns
+    #[arg(long)]
+    pub roster_header: bool,
+
+    /// Find the repos in the GitLab group, and their members, instead of using a roster
+    #[arg(long, conflicts_with = "roster")]
+    pub discover: bool,
+
+    /// File containing your GitLab token
+    #[arg(long)]
+    pub token_file: Option<String>,
+
+    /// Where the submission time comes from [default: commit]
+    #[arg(long, value_enum)]
+    pub timestamp_source: Option<TimestampSource>,
+
+    /// CSV file of per-student or per-group extensions
+    #[arg(long)]
+    pub extensions: Option<String>,
+
+    /// CSV file saying which section each student or group is in, for --section-due
+    #[arg(long)]
+    pub sections: Option<String>,
+
+    /// Due dates for sections that differ from --due, e.g. "002=2023-01-26 23:59", separated by
+    /// commas
+    #[arg(long, value_delimiter = ',', value_parser = parse_section_due)]
+    pub section_due: Vec<(String, DueDate)>,
+
+    /// Branch to evaluate [default: each project's default branch]
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Submit by tag instead: a tag name, or a glob like 'submit-*' for the latest match
+    #[arg(long, conflicts_with = "branch")]
+    pub tag: Option<String>,
+
+    /// Take the latest commit on any branch, not just the default one
+    #[arg(long, conflicts_with_all = ["branch", "tag"])]
+    pub all_branches: bool,
+
+    /// Count anything the starter commits can reach as starter code, not just the exact commits
+    #[arg(long)]
+    pub starter_ancestry: bool,
+
+    /// Stop if the roster has any problems, instead of fixing what can be fixed
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Check that every repo, branch and starter commit exists, and write nothing
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// How many repos to check at once [default: 1]
+    #[arg(long, short, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// Also write a detailed per-project report in this format
+    #[arg(long, value_enum)]
+    pub report: Option<ReportFormat>,
+
+    /// Ledger file to record this assessment's late days in, for the whole term
+    #[arg(long)]
+    pub ledger: Option<String>,
+
+    /// How lateness is charged: days, hours, days/N (e.g. days/2 for half days) or N%/hour
+    /// [default: days]
+    #[arg(long)]
+    pub lateness: Option<LatenessPolicy>,
+
+    /// Most lateness late days can cover, in the policy's units; anything later is listed as
+    /// exceeded instead of charged
+    #[arg(long)]
+    pub max_lateness: Option<f64>,
+
+    /// Usernames or emails of staff and bots whose commits and pushes don't count, separated by
+    /// commas
+    #[arg(long, value_delimiter = ',')]
+    pub staff: Vec<String>,
+
+    /// Globs of the graded paths, separated by commas; commits that change none of them don't
+    /// count [default: everything]
+    #[arg(long, value_delimiter = ',')]
+    pub include: Vec<String>,
+
+    /// Globs of paths that aren't graded, separated by commas, e.g. README.md,.gitignore
+    #[arg(long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Default, clap::Args)]
+pub struct BalanceArgs {
+    /// Ledger file written by run
+    #[arg(long)]
+    pub ledger: Option<String>,
+
+    /// How many late days each student gets for the term
+    #[arg(long)]
+    pub allowance: Option<f64>,
+}
+
+impl RunArgs {
+    pub fn designation(&self) -> Option<&String> {
+        self.assessment.as_ref().or(self.designation.as_ref())
+    }
+}
+
+const POSITIONAL_FLAGS: [&str; 9] = [
+    "--designation",
+    "--starter-commit",
+    "--group",
+    "--due",
+    "--tolerance",
+    "--roster",
+    "--token-file",
+    "--timestamp-source",
+    "--extensions",
+];
+
+pub fn parse_args(args: Vec<String>) -> Cli {
+    if is_positional_form(&args) {
+        Cli::parse_from(positional_to_named(&args))
+    } else {
+        Cli::parse_from(args)
+    }
+}
+
+// The original interface: seven positional arguments plus the optional timestamp source and
+// extensions file. Scripts written against it should keep working.
+pub fn is_positional_form(args: &[String]) -> bool {
+    if args.len() < 8 || args.len() > 10 {
+        return false;
+    }
+    let first = args.get(1).unwrap();
+    !first.starts_with('-') && Cli::command().find_subcommand(first).is_none()
+}
+
+fn positional_to_named(args: &[String]) -> Vec<String> {
+    let mut named = vec![args.first().unwrap().clone(), String::from("run")];
+    for (flag, value) in POSITIONAL_FLAGS.iter().zip(args.iter().skip(1)) {
+        named.push(flag.to_string());
+        named.push(value.clone());
+    }
+    named
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::America::Vancouver;
+    use clap::{CommandFactory, Parser};
+
+    use crate::cli::{is_positional_form, parse_args, positional_to_named, Cli, Command};
+    use crate::TimestampSource;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn positional_form_expects_8_to_10() {
+        let args1 = vec![String::from("a1"); 8];
+        let args2 = vec![String::from("a1"); 7];
+        let args3 = vec![String::from("a1"); 11];
+        let args4 = vec![String::from("a1"); 1];
+        let args5 = vec![String::from("a1"); 9];
+        let args6 = vec![String::from("a1"); 10];
+
+        assert!(is_positional_form(&args1));
+        assert!(!is_positional_form(&args2));
+        assert!(!is_positional_form(&args3));
+        assert!(!is_positional_form(&args4));
+        assert!(is_positional_form(&args5));
+        assert!(is_positional_form(&args6));
+    }
+
+    #[test]
+    fn positional_form_excludes_subcommands_and_flags() {
+        let mut args = vec![String::from("a1"); 8];
+        args[1] = String::from("run");
+        assert!(!is_positional_form(&args));
+        args[1] = String::from("--designation");
+        assert!(!is_positional_form(&args));
+    }
+
+    #[test]
+    fn parse_positional_args() {
+        let args = to_args(&[
+            "cmd",
+            "a1",
+            "e308eadf8d161c28edbf1076684eb4f7",
+            "ece459-1231",
+            "2023-01-27 14:30",
+            "15",
+            "csvfile.csv",
+            "tokenfile.csv",
+            "push",
+        ]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(Some(&String::from("a1")), run_args.designation());
+        assert_eq!(
+            vec![String::from("e308eadf8d161c28edbf1076684eb4f7")],
+            run_args.starter_commit
+        );
+        assert_eq!(Some(String::from("ece459-1231")), run_args.group);
+        assert_eq!(Some("2023-01-27 14:30".parse().unwrap()), run_args.due);
+        assert_eq!(Some(15), run_args.tolerance);
+        assert_eq!(Some(String::from("csvfile.csv")), run_args.roster);
+        assert_eq!(Some(String::from("tokenfile.csv")), run_args.token_file);
+        assert_eq!(Some(TimestampSource::Push), run_args.timestamp_source);
+        assert_eq!(None, run_args.extensions);
+    }
+
+    #[test]
+    fn parse_named_args_in_any_order() {
+        let args = to_args(&[
+            "cmd",
+            "run",
+            "--token-file",
+            "tokenfile.csv",
+            "--roster",
+            "csvfile.csv",
+            "--tolerance",
+            "15",
+            "--due",
+            "2023-01-27 14:30",
+            "--group",
+            "ece459-1231",
+            "--designation",
+            "a1",
+            "--starter-commit",
+            "e308eadf8d161c28edbf1076684eb4f7",
+            "--extensions",
+            "extensions.csv",
+        ]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(Some(&String::from("a1")), run_args.designation());
+        assert_eq!(Some(String::from("ece459-1231")), run_args.group);
+        assert_eq!(Some(15), run_args.tolerance);
+        assert_eq!(None, run_args.timestamp_source);
+        assert_eq!(Some(String::from("extensions.csv")), run_args.extensions);
+    }
+
+    #[test]
+    fn run_can_name_just_the_assessment() {
+        let args = to_args(&["cmd", "run", "a2", "--tolerance", "30"]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(Some(&String::from("a2")), run_args.designation());
+        assert_eq!(Some(30), run_args.tolerance);
+        assert!(run_args.starter_commit.is_empty());
+    }
+
+    #[test]
+    fn parse_balance_args() {
+        let args = to_args(&[
+            "cmd",
+            "balance",
+            "--ledger",
+            "ledger.csv",
+            "--allowance",
+            "5",
+        ]);
+
+        let Command::Balance(balance_args) = parse_args(args).command else {
+            panic!("Expected the balance command");
+        };
+
+        assert_eq!(Some(String::from("ledger.csv")), balance_args.ledger);
+        assert_eq!(Some(5.0), balance_args.allowance);
+    }
+
+    #[test]
+    fn due_date_can_have_an_offset_and_a_timezone() {
+        let args = to_args(&[
+            "cmd",
+            "run",
+            "a2",
+            "--due",
+            "2023-01-27T23:59:00-08:00",
+            "--timezone",
+            "America/Vancouver",
+        ]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(
+            Some("2023-01-27T23:59-08:00".parse().unwrap()),
+            run_args.due
+        );
+        assert_eq!(Some(Vancouver), run_args.timezone);
+    }
+
+    #[test]
+    fn unknown_timezone_is_an_error() {
+        let args = to_args(&["cmd", "run", "a2", "--timezone", "Mars/Olympus_Mons"]);
+
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn section_due_dates_are_a_comma_separated_list() {
+        let args = to_args(&[
+            "cmd",
+            "run",
+            "a2",
+            "--sections",
+            "sections.csv",
+            "--section-due",
+            "001=2023-01-26 23:59,002=2023-01-27T23:59-05:00",
+        ]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(Some(String::from("sections.csv")), run_args.sections);
+        assert_eq!(
+            vec![
+                (String::from("001"), "2023-01-26 23:59".parse().unwrap()),
+                (
+                    String::from("002"),
+                    "2023-01-27T23:59-05:00".parse().unwrap()
+                )
+            ],
+            run_args.section_due
+        );
+    }
+
+    #[test]
+    fn staff_is_a_comma_separated_list() {
+        let args = to_args(&["cmd", "run", "a2", "--staff", "ta1,ci-bot@example.com"]);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+
+        assert_eq!(vec!["ta1", "ci-bot@example.com"], run_args.staff);
+    }
+
+    #[test]
+    fn assessment_and_designation_conflict() {
+        let args = to_args(&["cmd", "run", "a2", "--designation", "a1"]);
+
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn swapped_due_date_and_tolerance_is_an_error_not_a_panic() {
+        let args = to_args(&[
+            "cmd",
+            "run",
+            "--designation",
+            "a1",
+            "--starter-commit",
+            "e308eadf8d161c28edbf1076684eb4f7",
+            "--group",
+            "ece459-1231",
+            "--due",
+            "15",
+            "--tolerance",
+            "2023-01-27 14:30",
+            "--roster",
+            "csvfile.csv",
+            "--token-file",
+            "tokenfile.csv",
+        ]);
+
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn unknown_timestamp_source_is_an_error() {
+        let args = to_args(&[
+            "cmd",
+            "a1",
+            "e308eadf8d161c28edbf1076684eb4f7",
+            "ece459-1231",
+            "2023-01-27 14:30",
+            "15",
+            "csvfile.csv",
+            "tokenfile.csv",
+            "pushed",
+        ]);
+
+        assert!(Cli::try_parse_from(positional_to_named(&args)).is_err());
+    }
+
+    #[test]
+    fn tag_timestamp_source_cant_be_asked_for() {
+        let args = to_args(&["cmd", "run", "a2", "--timestamp-source", "tag"]);
+
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn connection_options_can_come_after_the_subcommand() {
+        let args = to_args(&[
+            "cmd",
+            "run",
+            "--designation",
+            "a1",
+            "--starter-commit",
+            "e308eadf8d161c28edbf1076684eb4f7",
+            "--group",
+            "ece459-1231",
+            "--due",
+            "2023-01-27 14:30",
+            "--roster",
+            "csvfile.csv",
+            "--token-file",
+            "tokenfile.csv",
+            "--host",
+            "localhost:8080",
+            "--insecure",
+        ]);
+
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        assert_eq!(Some(String::from("localhost:8080")), cli.host);
+        assert!(cli.insecure);
+    }
+}
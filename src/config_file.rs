@@ -0,0 +1,619 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+
+use crate::cli::{BalanceArgs, RunArgs};
+use crate::due_date::{parse_timezone, DueDate, DEFAULT_TIMEZONE};
+use crate::error::Error;
+use crate::lateness::LatenessPolicy;
+use crate::report::ReportFormat;
+use crate::TimestampSource;
+
+pub const DEFAULT_CONFIG_FILE_NAME: &str = "late-days.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub gitlab: GitLabSection,
+    #[serde(default)]
+    pub course: CourseSection,
+    #[serde(default)]
+    pub assessment: Vec<Assessment>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitLabSection {
+    pub host: Option<String>,
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CourseSection {
+    pub group: Option<String>,
+    pub timezone: Option<String>,
+    pub token_file: Option<String>,
+    pub timestamp_source: Option<TimestampSource>,
+    pub jobs: Option<u16>,
+    pub report: Option<ReportFormat>,
+    pub ledger: Option<String>,
+    pub allowance: Option<f64>,
+    pub lateness: Option<LatenessPolicy>,
+    pub max_lateness: Option<f64>,
+    #[serde(default)]
+    pub staff: Vec<String>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub roster_header: bool,
+    pub sections: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assessment {
+    pub designation: String,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub starter_commit: Vec<String>,
+    pub due: Option<DueDate>,
+    pub timezone: Option<String>,
+    pub tolerance: Option<u64>,
+    pub roster: Option<String>,
+    #[serde(default)]
+    pub roster_header: bool,
+    #[serde(default)]
+    pub discover: bool,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub all_branches: bool,
+    #[serde(default)]
+    pub starter_ancestry: bool,
+    pub extensions: Option<String>,
+    pub sections: Option<String>,
+    #[serde(default)]
+    pub section_due: BTreeMap<String, DueDate>,
+    pub timestamp_source: Option<TimestampSource>,
+    pub lateness: Option<LatenessPolicy>,
+    pub max_lateness: Option<f64>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+// Everything needed for one run, after the command line has been laid over the config file.
+#[derive(Debug)]
+pub struct RunSettings {
+    pub designation: String,
+    pub starter_commits: Vec<String>,
+    pub starter_ancestry: bool,
+    pub group: String,
+    // What local due dates, extensions and the reports' local times are in.
+    pub timezone: Tz,
+    pub due: DateTime<Tz>,
+    pub tolerance: u64,
+    // None means the repos are discovered from the group instead.
+    pub roster: Option<String>,
+    pub roster_header: bool,
+    pub token_file: String,
+    pub timestamp_source: TimestampSource,
+    pub extensions: Option<String>,
+    pub sections: Option<String>,
+    // Only the sections whose due date differs from the regular one.
+    pub section_due: BTreeMap<String, DateTime<Tz>>,
+    // None means whatever each project's default branch is.
+    pub branch: Option<String>,
+    // A tag name or glob; when set, the submission is the latest matching tag, not a branch.
+    pub tag: Option<String>,
+    pub all_branches: bool,
+    pub jobs: usize,
+    pub report: Option<ReportFormat>,
+    pub ledger: Option<String>,
+    pub lateness: LatenessPolicy,
+    pub max_lateness: Option<f64>,
+    pub staff: Vec<String>,
+    // Whether roster problems stop the run.
+    pub strict: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+// starter_commit = "..." for the usual single commit, or a list once the starter code has been
+// updated.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
+// An explicitly requested config file has to exist; the default one is optional.
+pub fn load_config_file(filename: Option<&String>) -> Result<ConfigFile, Error> {
+    let filename = match filename {
+        Some(filename) => filename.as_str(),
+        None if Path::new(DEFAULT_CONFIG_FILE_NAME).exists() => DEFAULT_CONFIG_FILE_NAME,
+        None => return Ok(ConfigFile::default()),
+    };
+    let contents = fs::read_to_string(filename)
+        .map_err(|e| Error::io(format!("Unable to read config file {filename}"), e))?;
+    toml::from_str(&contents)
+        .map_err(|e| Error::BadArgs(format!("Invalid config file {filename}: {e}")))
+}
+
+pub fn resolve_run_settings(
+    run_args: &RunArgs,
+    config_file: &ConfigFile,
+) -> Result<RunSettings, Error> {
+    let designation = run_args.designation().ok_or_else(|| {
+        Error::BadArgs(String::from(
+            "Which assessment? Give its designation, e.g. run a1",
+        ))
+    })?;
+    let assessment = config_file
+        .assessment
+        .iter()
+        .find(|assessment| &assessment.designation == designation);
+    let missing = |option: &str, key: &str| {
+        Error::BadArgs(format!(
+            "No {option} given for {designation}; pass --{option} or set {key} in the config file"
+        ))
+    };
+
+    let timezone = match run_args.timezone {
+        Some(timezone) => timezone,
+        None => assessment
+            .and_then(|a| a.timezone.as_ref())
+            .or(config_file.course.timezone.as_ref())
+            .map(|timezone| {
+                parse_timezone(timezone)
+                    .map_err(|e| Error::BadArgs(format!("Invalid timezone for {designation}: {e}")))
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_TIMEZONE),
+    };
+    let due = run_args
+        .due
+        .or_else(|| assessment.and_then(|a| a.due))
+        .ok_or_else(|| missing("due", "due"))?
+        .in_timezone(timezone)
+        .map_err(|e| Error::BadArgs(format!("Invalid due date for {designation}: {e}")))?;
+
+    let section_due = if run_args.section_due.is_empty() {
+        assessment
+            .map(|a| a.section_due.clone().into_iter().collect())
+            .unwrap_or_default()
+    } else {
+        run_args.section_due.clone()
+    };
+    let section_due = section_due
+        .into_iter()
+        .map(|(section, due)| {
+            let due = due.in_timezone(timezone).map_err(|e| {
+                Error::BadArgs(format!(
+                    "Invalid due date for section {section} of {designation}: {e}"
+                ))
+            })?;
+            Ok((section, due))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let discover =
+        run_args.discover || (run_args.roster.is_none() && assessment.is_some_and(|a| a.discover));
+    let roster = match (&run_args.roster, assessment.and_then(|a| a.roster.as_ref())) {
+        _ if discover => None,
+        (Some(roster), _) | (None, Some(roster)) => Some(roster.clone()),
+        (None, None) => return Err(missing("roster", "roster (or discover = true)")),
+    };
+
+    Ok(RunSettings {
+        designation: designation.clone(),
+        starter_commits: match (&run_args.starter_commit, assessment) {
+            (starter_commits, _) if !starter_commits.is_empty() => starter_commits.clone(),
+            (_, Some(assessment)) if !assessment.starter_commit.is_empty() => {
+                assessment.starter_commit.clone()
+            }
+            _ => return Err(missing("starter-commit", "starter_commit")),
+        },
+        starter_ancestry: run_args.starter_ancestry
+            || assessment.is_some_and(|a| a.starter_ancestry),
+        group: run_args
+            .group
+            .clone()
+            .or_else(|| config_file.course.group.clone())
+            .ok_or_else(|| missing("group", "[course] group"))?,
+        timezone,
+        due,
+        tolerance: run_args
+            .tolerance
+            .or_else(|| assessment.and_then(|a| a.tolerance))
+            .unwrap_or(0),
+        roster,
+        roster_header: run_args.roster_header
+            || assessment.is_some_and(|a| a.roster_header)
+            || config_file.course.roster_header,
+        token_file: run_args
+            .token_file
+            .clone()
+            .or_else(|| config_file.course.token_file.clone())
+            .ok_or_else(|| missing("token-file", "[course] token_file"))?,
+        timestamp_source: run_args
+            .timestamp_source
+            .or_else(|| assessment.and_then(|a| a.timestamp_source))
+            .or(config_file.course.timestamp_source)
+            .unwrap_or(TimestampSource::Commit),
+        extensions: run_args
+            .extensions
+            .clone()
+            .or_else(|| assessment.and_then(|a| a.extensions.clone())),
+        sections: run_args
+            .sections
+            .clone()
+            .or_else(|| assessment.and_then(|a| a.sections.clone()))
+            .or_else(|| config_file.course.sections.clone()),
+        section_due,
+        branch: run_args
+            .branch
+            .clone()
+            .or_else(|| assessment.and_then(|a| a.branch.clone())),
+        tag: run_args
+            .tag
+            .clone()
+            // A --branch on the command line beats a tag from the course file.
+            .or_else(|| {
+                assessment
+                    .filter(|_| run_args.branch.is_none())
+                    .and_then(|a| a.tag.clone())
+            }),
+        all_branches: run_args.all_branches
+            || (run_args.branch.is_none()
+                && run_args.tag.is_none()
+                && assessment.is_some_and(|a| a.all_branches)),
+        jobs: run_args.jobs.or(config_file.course.jobs).unwrap_or(1) as usize,
+        report: run_args.report.or(config_file.course.report),
+        ledger: run_args
+            .ledger
+            .clone()
+            .or_else(|| config_file.course.ledger.clone()),
+        lateness: run_args
+            .lateness
+            .or_else(|| assessment.and_then(|a| a.lateness))
+            .or(config_file.course.lateness)
+            .unwrap_or(LatenessPolicy::Days),
+        max_lateness: run_args
+            .max_lateness
+            .or_else(|| assessment.and_then(|a| a.max_lateness))
+            .or(config_file.course.max_lateness),
+        staff: if run_args.staff.is_empty() {
+            config_file.course.staff.clone()
+        } else {
+            run_args.staff.clone()
+        },
+        strict: run_args.strict || config_file.course.strict,
+        include: match (&run_args.include, assessment) {
+            (include, Some(assessment)) if include.is_empty() => assessment.include.clone(),
+            (include, _) => include.clone(),
+        },
+        exclude: match (&run_args.exclude, assessment) {
+            (exclude, Some(assessment)) if exclude.is_empty() => assessment.exclude.clone(),
+            (exclude, _) => exclude.clone(),
+        },
+    })
+}
+
+// The ledger file and the late day allowance, for the balance command.
+pub fn resolve_balance_settings(
+    balance_args: &BalanceArgs,
+    config_file: &ConfigFile,
+) -> Result<(String, f64), Error> {
+    let ledger = balance_args
+        .ledger
+        .clone()
+        .or_else(|| config_file.course.ledger.clone())
+        .ok_or_else(|| {
+            Error::BadArgs(String::from(
+                "No ledger given; pass --ledger or set [course] ledger in the config file",
+            ))
+        })?;
+    let allowance = balance_args
+        .allowance
+        .or(config_file.course.allowance)
+        .ok_or_else(|| {
+            Error::BadArgs(String::from(
+                "No allowance given; pass --allowance or set [course] allowance in the config file",
+            ))
+        })?;
+    Ok((ledger, allowance))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::America::{Halifax, Toronto, Vancouver};
+
+    use crate::cli::{BalanceArgs, RunArgs};
+    use crate::config_file::{load_config_file, resolve_balance_settings, resolve_run_settings};
+    use crate::error::Error;
+    use crate::lateness::LatenessPolicy;
+    use crate::report::ReportFormat;
+    use crate::TimestampSource;
+
+    #[test]
+    fn can_load_gitlab_section() {
+        let filename = String::from("test/resources/late-days.toml");
+
+        let config_file = load_config_file(Some(&filename)).unwrap();
+
+        assert_eq!(
+            Some(String::from("gitlab.example.com")),
+            config_file.gitlab.host
+        );
+        assert!(!config_file.gitlab.insecure);
+    }
+
+    #[test]
+    fn explicit_config_file_must_exist() {
+        let filename = String::from("test/resources/missing.toml");
+
+        let error = load_config_file(Some(&filename)).unwrap_err();
+
+        assert!(matches!(error, Error::Io { .. }));
+        assert!(error
+            .to_string()
+            .starts_with("Unable to read config file test/resources/missing.toml"));
+    }
+
+    #[test]
+    fn can_load_course_and_assessments() {
+        let filename = String::from("test/resources/course.toml");
+
+        let config_file = load_config_file(Some(&filename)).unwrap();
+
+        assert_eq!(Some(String::from("ece459-1231")), config_file.course.group);
+        assert_eq!(2, config_file.assessment.len());
+        assert_eq!("a2", config_file.assessment[1].designation);
+        assert_eq!(
+            Some(String::from("master")),
+            config_file.assessment[1].branch
+        );
+    }
+
+    #[test]
+    fn run_settings_come_from_the_assessment() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a2")),
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!("a2", settings.designation);
+        assert_eq!(
+            vec![
+                "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+                "9f3b2a1c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f80"
+            ],
+            settings.starter_commits
+        );
+        assert!(settings.starter_ancestry);
+        assert_eq!("ece459-1231", settings.group);
+        assert_eq!(
+            "2023-02-10 23:59 AST",
+            settings.due.format("%Y-%m-%d %H:%M %Z").to_string()
+        );
+        assert_eq!(30, settings.tolerance);
+        assert_eq!(Some(String::from("a2-groups.csv")), settings.roster);
+        assert!(settings.roster_header);
+        assert_eq!("token.git", settings.token_file);
+        assert_eq!(TimestampSource::Push, settings.timestamp_source);
+        assert_eq!(None, settings.extensions);
+        assert_eq!(Some(String::from("master")), settings.branch);
+        assert_eq!(4, settings.jobs);
+        assert_eq!(Some(ReportFormat::Json), settings.report);
+        assert_eq!(Some(String::from("ledger.csv")), settings.ledger);
+        assert_eq!(LatenessPolicy::FractionalDays(2), settings.lateness);
+        assert_eq!(Some(3.0), settings.max_lateness);
+        assert_eq!(vec!["ta1", "ci-bot@example.com"], settings.staff);
+    }
+
+    #[test]
+    fn command_line_overrides_the_assessment() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a1")),
+            tolerance: Some(5),
+            timestamp_source: Some(TimestampSource::Commit),
+            jobs: Some(8),
+            exclude: vec![String::from("*.md")],
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!(
+            vec!["79ca81e76a65ff5009596c6e60b99ad0"],
+            settings.starter_commits
+        );
+        assert!(!settings.starter_ancestry);
+        assert_eq!(5, settings.tolerance);
+        assert_eq!(TimestampSource::Commit, settings.timestamp_source);
+        assert_eq!(None, settings.branch);
+        assert_eq!(Some(String::from("submit-*")), settings.tag);
+        assert_eq!(8, settings.jobs);
+        assert_eq!(LatenessPolicy::Hours, settings.lateness);
+        assert_eq!(vec!["src/**", "Cargo.toml"], settings.include);
+        assert_eq!(vec!["*.md"], settings.exclude);
+        assert_eq!(Some(72.0), settings.max_lateness);
+        assert_eq!(Some(String::from("a1-extensions.csv")), settings.extensions);
+    }
+
+    #[test]
+    fn command_line_branch_overrides_the_assessment_tag() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a1")),
+            branch: Some(String::from("main")),
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!(Some(String::from("main")), settings.branch);
+        assert_eq!(None, settings.tag);
+        assert!(!settings.all_branches);
+    }
+
+    #[test]
+    fn timezone_comes_from_the_command_line_then_assessment_then_course() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let a1 = RunArgs {
+            assessment: Some(String::from("a1")),
+            ..Default::default()
+        };
+        let a2 = RunArgs {
+            assessment: Some(String::from("a2")),
+            ..Default::default()
+        };
+        let a2_in_toronto = RunArgs {
+            assessment: Some(String::from("a2")),
+            timezone: Some(Toronto),
+            ..Default::default()
+        };
+
+        let a1 = resolve_run_settings(&a1, &config_file).unwrap();
+        let a2 = resolve_run_settings(&a2, &config_file).unwrap();
+        let a2_in_toronto = resolve_run_settings(&a2_in_toronto, &config_file).unwrap();
+
+        assert_eq!(Vancouver, a1.timezone);
+        assert_eq!(
+            "2023-01-27 20:59 PST",
+            a1.due.format("%Y-%m-%d %H:%M %Z").to_string()
+        );
+        assert_eq!(Halifax, a2.timezone);
+        assert_eq!(
+            "2023-02-10 23:59 AST",
+            a2.due.format("%Y-%m-%d %H:%M %Z").to_string()
+        );
+        assert_eq!(Toronto, a2_in_toronto.timezone);
+    }
+
+    #[test]
+    fn due_date_in_the_dst_gap_is_reported() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a1")),
+            due: Some("2023-03-12 02:30".parse().unwrap()),
+            timezone: Some(Toronto),
+            ..Default::default()
+        };
+
+        let error = resolve_run_settings(&run_args, &config_file).unwrap_err();
+
+        assert!(matches!(error, Error::BadArgs(_)));
+        assert!(error
+            .to_string()
+            .starts_with("Invalid due date for a1: 2023-03-12 02:30 doesn't exist"));
+    }
+
+    #[test]
+    fn section_due_dates_come_from_the_assessment() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a2")),
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!(Some(String::from("sections.csv")), settings.sections);
+        assert_eq!(2, settings.section_due.len());
+        assert_eq!(
+            "2023-02-09 23:59 AST",
+            settings.section_due["001"]
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn command_line_section_due_dates_replace_the_assessment_ones() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a2")),
+            section_due: vec![(String::from("003"), "2023-02-11 12:00".parse().unwrap())],
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!(
+            vec!["003"],
+            settings.section_due.into_keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn discover_instead_of_roster() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a2")),
+            discover: true,
+            ..Default::default()
+        };
+
+        let settings = resolve_run_settings(&run_args, &config_file).unwrap();
+
+        assert_eq!(None, settings.roster);
+    }
+
+    #[test]
+    fn balance_settings_come_from_the_course() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+
+        let (ledger, allowance) =
+            resolve_balance_settings(&BalanceArgs::default(), &config_file).unwrap();
+
+        assert_eq!("ledger.csv", ledger);
+        assert_eq!(5.0, allowance);
+    }
+
+    #[test]
+    fn unknown_assessment_needs_everything_on_the_command_line() {
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/course.toml"))).unwrap();
+        let run_args = RunArgs {
+            assessment: Some(String::from("a3")),
+            ..Default::default()
+        };
+
+        let error = resolve_run_settings(&run_args, &config_file).unwrap_err();
+
+        assert!(matches!(error, Error::BadArgs(_)));
+        assert!(error
+            .to_string()
+            .starts_with("No due given for a3; pass --due"));
+    }
+}
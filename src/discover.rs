@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use gitlab::api::groups::members::GroupMembers;
+use gitlab::api::groups::projects::GroupProjects;
+use gitlab::api::projects::members::ProjectMembers;
+use gitlab::api::{paged, Pagination, Query};
+use gitlab::Gitlab;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::{GitLabConfig, Repo};
+
+const DEVELOPER_ACCESS: u64 = 30;
+const MAINTAINER_ACCESS: u64 = 40;
+
+#[derive(Debug, Deserialize)]
+struct GroupProject {
+    id: u64,
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    username: String,
+    access_level: u64,
+}
+
+// Every project in the group named {group}-{designation}-{label}, with its developers and
+// maintainers as the members. Anyone who is a member of the group itself is course staff and
+// gets left out, as does anyone with owner access.
+pub fn discover_repos(client: &Gitlab, config: &GitLabConfig) -> Result<Vec<Repo>, Error> {
+    let prefix = format!("{}-{}-", config.group_name, config.designation);
+    let staff = get_group_members(client, &config.group_name)?;
+
+    let projects_endpoint = GroupProjects::builder()
+        .group(config.group_name.as_str())
+        .search(prefix.as_str())
+        .simple(true)
+        .build()
+        .unwrap();
+    let projects: Vec<GroupProject> = paged(projects_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, &format!("projects in group {}", config.group_name)))?;
+
+    let mut repos = Vec::new();
+    for project in projects {
+        let Some(label) = project.path.strip_prefix(&prefix) else {
+            continue;
+        };
+        let members = get_student_members(client, project.id, &staff)?;
+        if members.is_empty() {
+            println!(
+                "Project {} has no student members; skipping it.",
+                project.path
+            );
+            continue;
+        }
+        repos.push(Repo {
+            label: String::from(label),
+            members,
+        });
+    }
+    repos.sort_by(|a, b| natural_key(&a.label).cmp(&natural_key(&b.label)));
+    Ok(repos)
+}
+
+fn get_group_members(client: &Gitlab, group_name: &str) -> Result<HashSet<String>, Error> {
+    let members_endpoint = GroupMembers::builder().group(group_name).build().unwrap();
+    let members: Vec<Member> = paged(members_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, &format!("members of group {group_name}")))?;
+    Ok(members.into_iter().map(|member| member.username).collect())
+}
+
+fn get_student_members(
+    client: &Gitlab,
+    project_id: u64,
+    staff: &HashSet<String>,
+) -> Result<Vec<String>, Error> {
+    let members_endpoint = ProjectMembers::builder()
+        .project(project_id)
+        .build()
+        .unwrap();
+    let members: Vec<Member> = paged(members_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, &format!("members of project {project_id}")))?;
+
+    let mut students: Vec<String> = members
+        .into_iter()
+        .filter(|member| {
+            member.access_level == DEVELOPER_ACCESS || member.access_level == MAINTAINER_ACCESS
+        })
+        .map(|member| member.username)
+        .filter(|username| !staff.contains(username))
+        .collect();
+    students.sort();
+    Ok(students)
+}
+
+// So that g2 sorts before g10.
+fn natural_key(label: &str) -> (&str, u64) {
+    let digits_start = label.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (text, number) = label.split_at(digits_start);
+    (text, number.parse().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use gitlab::Gitlab;
+    use httpmock::prelude::*;
+
+    use crate::discover::{discover_repos, natural_key};
+    use crate::tests::test_config;
+    use crate::{Repo, TimestampSource};
+
+    #[test]
+    fn natural_key_sorts_numbers_numerically() {
+        assert!(natural_key("g2") < natural_key("g10"));
+        assert!(natural_key("g10") < natural_key("h1"));
+        assert!(natural_key("jzarnett") < natural_key("username"));
+    }
+
+    #[test]
+    fn test_discover_repos() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let projects_json = fs::read_to_string("test/resources/examplegroupprojects.json")
+            .unwrap_or_else(|_| panic!("Unable to read group projects data"));
+        let group_members_json = fs::read_to_string("test/resources/examplegroupmembers.json")
+            .unwrap_or_else(|_| panic!("Unable to read group members data"));
+        let g2_members_json = fs::read_to_string("test/resources/exampleprojectmembers.json")
+            .unwrap_or_else(|_| panic!("Unable to read project members data"));
+
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_projects_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/groups/ece459/projects")
+                .query_param("search", "ece459-p-");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(projects_json);
+        });
+        let get_group_members_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/groups/ece459/members");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(group_members_json);
+        });
+        let get_g2_members_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/projects/6/members");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(g2_members_json);
+        });
+        let get_g10_members_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/projects/7/members");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"[{"id": 12, "username": "u3sernam", "access_level": 30}]"#);
+        });
+        let get_empty_members_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/projects/8/members");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"[{"id": 20, "username": "ta1", "access_level": 40}]"#);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config("ece459", "", TimestampSource::Commit);
+        config.designation = String::from("p");
+        config.starter_commits = Vec::new();
+        let repos = discover_repos(&gitlab, &config).unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_projects_mock.assert();
+        get_group_members_mock.assert();
+        get_g2_members_mock.assert();
+        get_g10_members_mock.assert();
+        get_empty_members_mock.assert();
+        assert_eq!(
+            vec![
+                Repo {
+                    label: String::from("g2"),
+                    members: vec![String::from("u2sernam"), String::from("username")],
+                },
+                Repo {
+                    label: String::from("g10"),
+                    members: vec![String::from("u3sernam")],
+                },
+            ],
+            repos
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::str::FromStr;
+
+use chrono::offset::LocalResult;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::DATE_TIME_FORMAT;
+
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Canada::Eastern;
+
+// How due dates with an explicit offset are shown in error messages, and the ISO 8601 form
+// without seconds that's accepted alongside RFC 3339.
+const OFFSET_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M%:z";
+
+// A due date as written. Local times are only pinned down once the course's timezone is known;
+// one with an explicit offset means the same instant wherever it's read.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum DueDate {
+    Local(NaiveDateTime),
+    Fixed(DateTime<FixedOffset>),
+}
+
+impl DueDate {
+    // Local times that the clocks skip over, or pass through twice, when daylight saving time
+    // starts or ends are errors rather than a guess.
+    pub fn in_timezone(&self, timezone: Tz) -> Result<DateTime<Tz>, String> {
+        let local = match self {
+            DueDate::Fixed(due) => return Ok(due.with_timezone(&timezone)),
+            DueDate::Local(local) => local,
+        };
+        let written = local.format(DATE_TIME_FORMAT);
+        match local.and_local_timezone(timezone) {
+            LocalResult::Single(due) => Ok(due),
+            LocalResult::Ambiguous(earlier, later) => Err(format!(
+                "{written} happens twice in {timezone} when the clocks go back; give an offset, \
+                 e.g. {} or {}",
+                earlier.format(OFFSET_DATE_TIME_FORMAT),
+                later.format(OFFSET_DATE_TIME_FORMAT)
+            )),
+            LocalResult::None => Err(format!(
+                "{written} doesn't exist in {timezone}; the clocks skip over it when they go \
+                 forward"
+            )),
+        }
+    }
+}
+
+impl FromStr for DueDate {
+    type Err = String;
+
+    fn from_str(due_date: &str) -> Result<Self, Self::Err> {
+        if let Ok(due) = DateTime::parse_from_rfc3339(due_date)
+            .or_else(|_| DateTime::parse_from_str(due_date, OFFSET_DATE_TIME_FORMAT))
+        {
+            return Ok(DueDate::Fixed(due));
+        }
+        NaiveDateTime::parse_from_str(due_date, DATE_TIME_FORMAT)
+            .map(DueDate::Local)
+            .map_err(|_| {
+                format!(
+                    "{due_date} is not in the format {DATE_TIME_FORMAT}, or RFC 3339 with an \
+                     offset"
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for DueDate {
+    type Error = String;
+
+    fn try_from(due_date: String) -> Result<Self, Self::Error> {
+        due_date.parse()
+    }
+}
+
+pub fn parse_timezone(timezone: &str) -> Result<Tz, String> {
+    timezone
+        .parse()
+        .map_err(|_| format!("Unknown timezone {timezone}; use an IANA name like America/Toronto"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::America::Vancouver;
+    use chrono_tz::Canada::Eastern;
+
+    use crate::due_date::{parse_timezone, DueDate};
+
+    const FORMAT: &str = "%Y-%m-%d %H:%M %Z";
+
+    #[test]
+    fn local_due_dates_are_in_the_given_timezone() {
+        let due: DueDate = "2023-01-27 14:30".parse().unwrap();
+
+        assert_eq!(
+            "2023-01-27 14:30 EST",
+            due.in_timezone(Eastern).unwrap().format(FORMAT).to_string()
+        );
+        assert_eq!(
+            "2023-01-27 14:30 PST",
+            due.in_timezone(Vancouver)
+                .unwrap()
+                .format(FORMAT)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn offsets_fix_the_instant() {
+        let rfc3339: DueDate = "2023-01-27T14:30:00-08:00".parse().unwrap();
+        let iso8601: DueDate = "2023-01-27T14:30-08:00".parse().unwrap();
+
+        assert_eq!(rfc3339, iso8601);
+        assert_eq!(
+            "2023-01-27 17:30 EST",
+            rfc3339
+                .in_timezone(Eastern)
+                .unwrap()
+                .format(FORMAT)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!("15".parse::<DueDate>().is_err());
+        assert!("2023-01-27".parse::<DueDate>().is_err());
+        assert!("2023-02-30 12:00".parse::<DueDate>().is_err());
+    }
+
+    #[test]
+    fn skipped_local_time_is_reported() {
+        let due: DueDate = "2023-03-12 02:30".parse().unwrap();
+
+        assert_eq!(
+            Err(String::from(
+                "2023-03-12 02:30 doesn't exist in Canada/Eastern; the clocks skip over it when \
+                 they go forward"
+            )),
+            due.in_timezone(Eastern)
+        );
+    }
+
+    #[test]
+    fn repeated_local_time_is_reported() {
+        let due: DueDate = "2023-11-05 01:30".parse().unwrap();
+
+        assert_eq!(
+            Err(String::from(
+                "2023-11-05 01:30 happens twice in Canada/Eastern when the clocks go back; give \
+                 an offset, e.g. 2023-11-05T01:30-04:00 or 2023-11-05T01:30-05:00"
+            )),
+            due.in_timezone(Eastern)
+        );
+        let due: DueDate = "2023-11-05T01:30-05:00".parse().unwrap();
+        assert_eq!(
+            "2023-11-05 01:30 EST",
+            due.in_timezone(Eastern).unwrap().format(FORMAT).to_string()
+        );
+    }
+
+    #[test]
+    fn timezones_are_iana_names() {
+        assert_eq!(Ok(Vancouver), parse_timezone("America/Vancouver"));
+        assert!(parse_timezone("Eastern").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+// Endpoints the gitlab crate doesn't provide (yet), written the same way as the ones it does.
+
+use gitlab::api::endpoint_prelude::*;
+
+pub struct ProjectEvents {
+    pub project: u64,
+    pub action: &'static str,
+}
+
+impl Endpoint for ProjectEvents {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/events", self.project).into()
+    }
+
+    fn parameters(&self) -> QueryParams<'_> {
+        let mut params = QueryParams::default();
+        params.push("action", self.action);
+        params
+    }
+}
+
+impl Pageable for ProjectEvents {}
+
+pub struct CommitDiff<'a> {
+    pub project: u64,
+    pub commit: &'a str,
+}
+
+impl Endpoint for CommitDiff<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/repository/commits/{}/diff",
+            self.project, self.commit
+        )
+        .into()
+    }
+}
+
+impl Pageable for CommitDiff<'_> {}
+
+pub struct Compare<'a> {
+    pub project: u64,
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl Endpoint for Compare<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/repository/compare", self.project).into()
+    }
+
+    fn parameters(&self) -> QueryParams<'_> {
+        let mut params = QueryParams::default();
+        params.push("from", self.from);
+        params.push("to", self.to);
+        params
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::{Display, Formatter};
+use std::{fmt, io};
+
+use gitlab::api::ApiError;
+
+// Everything that can stop a run, or stop one project from being checked. Each kind has its own
+// exit code (listed in the README), so scripts can tell them apart without parsing messages.
+#[derive(Debug)]
+pub enum Error {
+    // An option or config file setting that's missing or doesn't make sense, or a file one of
+    // them names (extensions, sections, ledger) that isn't laid out right.
+    BadArgs(String),
+    // A roster that can't be read as one, or that --strict found problems in.
+    Roster(String),
+    // GitLab wouldn't let us in with the token.
+    Auth(String),
+    ProjectNotFound(String),
+    BranchNotFound { branch: String, reason: String },
+    // Any other request to GitLab that failed.
+    GitLab(String),
+    Io { context: String, source: io::Error },
+    // Some projects couldn't be checked (or, in a dry run, have problems); the first of them in
+    // roster order stands for the rest.
+    Incomplete { summary: String, first: Box<Error> },
+}
+
+impl Error {
+    pub fn io(context: String, source: io::Error) -> Error {
+        Error::Io { context, source }
+    }
+
+    // A failed request for something that ought to be there. Only a 404 means it isn't; the rest
+    // are treated as for any other request.
+    pub fn lookup<E>(e: ApiError<E>, what: &str, not_found: impl FnOnce(String) -> Error) -> Error
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        match status(&e) {
+            Some(404) => not_found(e.to_string()),
+            _ => Error::gitlab(e, what),
+        }
+    }
+
+    // A token that's expired or lost its access partway through the run is an auth failure, not
+    // GitLab having trouble.
+    pub fn gitlab<E>(e: ApiError<E>, what: &str) -> Error
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        match status(&e) {
+            Some(401 | 403) => Error::Auth(format!("{what} unavailable with this token: {e}")),
+            _ => Error::GitLab(format!("{what} unavailable: {e}")),
+        }
+    }
+
+    pub fn first_of(errors: Vec<Error>, summary: String) -> Result<(), Error> {
+        match errors.into_iter().next() {
+            Some(first) => Err(Error::Incomplete {
+                summary,
+                first: Box::new(first),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::GitLab(_) => 1,
+            Error::BadArgs(_) => 2,
+            Error::Roster(_) => 3,
+            Error::Auth(_) => 4,
+            Error::ProjectNotFound(_) => 5,
+            Error::BranchNotFound { .. } => 6,
+            Error::Io { .. } => 7,
+            Error::Incomplete { first, .. } => first.exit_code(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BadArgs(message)
+            | Error::Roster(message)
+            | Error::Auth(message)
+            | Error::GitLab(message) => write!(f, "{message}"),
+            Error::ProjectNotFound(reason) => write!(f, "project not found: {reason}"),
+            Error::BranchNotFound { branch, reason } => {
+                write!(f, "branch {branch} not found: {reason}")
+            }
+            Error::Io { context, source } => write!(f, "{context}: {source}"),
+            Error::Incomplete { summary, .. } => write!(f, "{summary}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// GitLab's JSON errors only give the status in the message ("404 Project Not Found"); anything
+// that isn't JSON, such as a proxy's error page, comes with the status itself.
+fn status<E>(e: &ApiError<E>) -> Option<u16>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    match e {
+        ApiError::GitlabService { status, .. } => Some(status.as_u16()),
+        ApiError::Gitlab { msg } => msg.split_whitespace().next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::error::Error;
+
+    #[test]
+    fn each_kind_has_its_own_exit_code() {
+        let errors = [
+            Error::GitLab(String::from("push events unavailable")),
+            Error::BadArgs(String::from("No due given")),
+            Error::Roster(String::from("1 problem(s) in roster.csv")),
+            Error::Auth(String::from("Unable to log in")),
+            Error::ProjectNotFound(String::from("404")),
+            Error::BranchNotFound {
+                branch: String::from("main"),
+                reason: String::from("404"),
+            },
+            Error::io(
+                String::from("Failed to read roster.csv"),
+                io::Error::from(io::ErrorKind::NotFound),
+            ),
+        ];
+
+        let codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], codes);
+    }
+
+    #[test]
+    fn incomplete_runs_exit_like_their_first_failure() {
+        let failures = vec![
+            Error::ProjectNotFound(String::from("404 Project Not Found")),
+            Error::GitLab(String::from("push events unavailable")),
+        ];
+
+        let error = Error::first_of(failures, String::from("2 project(s) could not be checked"))
+            .unwrap_err();
+
+        assert_eq!(5, error.exit_code());
+        assert_eq!("2 project(s) could not be checked", error.to_string());
+        assert!(Error::first_of(Vec::new(), String::new()).is_ok());
+    }
+
+    #[test]
+    fn gitlab_lookups_keep_their_messages() {
+        let branch = Error::BranchNotFound {
+            branch: String::from("main"),
+            reason: String::from("404 Branch Not Found"),
+        };
+
+        assert_eq!(
+            "project not found: 404 Project Not Found",
+            Error::ProjectNotFound(String::from("404 Project Not Found")).to_string()
+        );
+        assert_eq!(
+            "branch main not found: 404 Branch Not Found",
+            branch.to_string()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::MINS_PER_DAY;
+
+const MINS_PER_HOUR: i64 = 60;
+
+// How lateness turns into a charge. Every policy charges per started unit, so being late at
+// all costs one unit, the same way the original whole-day rule works.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum LatenessPolicy {
+    // 1 + floor(minutes / MINS_PER_DAY) days; what the tool has always done.
+    Days,
+    // Started hours.
+    Hours,
+    // Started 1/N days, so 2 charges in half days. Written days/N.
+    FractionalDays(u32),
+    // A percentage penalty per started hour, up to 100. Written N%/hour.
+    PercentPerHour(f64),
+}
+
+impl LatenessPolicy {
+    pub fn charge(&self, late_by: chrono::Duration) -> f64 {
+        if late_by <= chrono::Duration::zero() {
+            return 0.0;
+        }
+        let minutes = late_by.num_minutes();
+        match self {
+            LatenessPolicy::Days => started(minutes, MINS_PER_DAY as i64) as f64,
+            LatenessPolicy::Hours => started(minutes, MINS_PER_HOUR) as f64,
+            LatenessPolicy::FractionalDays(parts) => {
+                let unit = MINS_PER_DAY as i64 / *parts as i64;
+                round_to_hundredths(started(minutes, unit) as f64 / *parts as f64)
+            }
+            LatenessPolicy::PercentPerHour(percent) => {
+                (started(minutes, MINS_PER_HOUR) as f64 * percent).min(100.0)
+            }
+        }
+    }
+
+    // Whether charges are in days, which is all the ledger and its day allowance understand.
+    pub fn counts_days(&self) -> bool {
+        matches!(
+            self,
+            LatenessPolicy::Days | LatenessPolicy::FractionalDays(_)
+        )
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            LatenessPolicy::Days | LatenessPolicy::FractionalDays(_) => "day(s)",
+            LatenessPolicy::Hours => "hour(s)",
+            LatenessPolicy::PercentPerHour(_) => "% penalty",
+        }
+    }
+}
+
+fn started(minutes: i64, unit: i64) -> i64 {
+    1 + minutes / unit
+}
+
+fn round_to_hundredths(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl Display for LatenessPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LatenessPolicy::Days => write!(f, "days"),
+            LatenessPolicy::Hours => write!(f, "hours"),
+            LatenessPolicy::FractionalDays(parts) => write!(f, "days/{parts}"),
+            LatenessPolicy::PercentPerHour(percent) => write!(f, "{percent}%/hour"),
+        }
+    }
+}
+
+impl FromStr for LatenessPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("Invalid lateness policy {policy}; use days, hours, days/N or N%/hour");
+        match policy {
+            "days" => Ok(LatenessPolicy::Days),
+            "hours" => Ok(LatenessPolicy::Hours),
+            _ => {
+                if let Some(parts) = policy.strip_prefix("days/") {
+                    let parts: u32 = parts.parse().map_err(|_| invalid())?;
+                    if parts == 0 || !(MINS_PER_DAY as u32).is_multiple_of(parts) {
+                        return Err(format!(
+                            "Invalid lateness policy {policy}; a day can't be split into {parts} equal parts"
+                        ));
+                    }
+                    Ok(LatenessPolicy::FractionalDays(parts))
+                } else if let Some(percent) = policy.strip_suffix("%/hour") {
+                    let percent: f64 = percent.parse().map_err(|_| invalid())?;
+                    if !(percent > 0.0 && percent <= 100.0) {
+                        return Err(invalid());
+                    }
+                    Ok(LatenessPolicy::PercentPerHour(percent))
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for LatenessPolicy {
+    type Error = String;
+
+    fn try_from(policy: String) -> Result<Self, Self::Error> {
+        policy.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::lateness::LatenessPolicy;
+
+    #[test]
+    fn nothing_charged_when_on_time() {
+        for policy in [
+            LatenessPolicy::Days,
+            LatenessPolicy::Hours,
+            LatenessPolicy::FractionalDays(2),
+            LatenessPolicy::PercentPerHour(10.0),
+        ] {
+            assert_eq!(0.0, policy.charge(Duration::zero()));
+            assert_eq!(0.0, policy.charge(Duration::minutes(-5)));
+        }
+    }
+
+    #[test]
+    fn days_charge_per_started_day() {
+        assert_eq!(1.0, LatenessPolicy::Days.charge(Duration::seconds(30)));
+        assert_eq!(1.0, LatenessPolicy::Days.charge(Duration::hours(23)));
+        assert_eq!(2.0, LatenessPolicy::Days.charge(Duration::hours(24)));
+        assert_eq!(3.0, LatenessPolicy::Days.charge(Duration::hours(49)));
+    }
+
+    #[test]
+    fn hours_charge_per_started_hour() {
+        assert_eq!(1.0, LatenessPolicy::Hours.charge(Duration::minutes(5)));
+        assert_eq!(1.0, LatenessPolicy::Hours.charge(Duration::minutes(59)));
+        assert_eq!(2.0, LatenessPolicy::Hours.charge(Duration::minutes(60)));
+        assert_eq!(26.0, LatenessPolicy::Hours.charge(Duration::hours(25)));
+    }
+
+    #[test]
+    fn half_days_charge_per_started_half_day() {
+        let policy = LatenessPolicy::FractionalDays(2);
+
+        assert_eq!(0.5, policy.charge(Duration::minutes(5)));
+        assert_eq!(1.0, policy.charge(Duration::hours(12)));
+        assert_eq!(1.5, policy.charge(Duration::hours(30)));
+    }
+
+    #[test]
+    fn thirds_of_a_day_are_rounded() {
+        let policy = LatenessPolicy::FractionalDays(3);
+
+        assert_eq!(0.33, policy.charge(Duration::hours(1)));
+        assert_eq!(0.67, policy.charge(Duration::hours(9)));
+    }
+
+    #[test]
+    fn percent_penalty_per_started_hour_up_to_100() {
+        let policy = LatenessPolicy::PercentPerHour(10.0);
+
+        assert_eq!(10.0, policy.charge(Duration::minutes(5)));
+        assert_eq!(30.0, policy.charge(Duration::minutes(150)));
+        assert_eq!(100.0, policy.charge(Duration::hours(30)));
+    }
+
+    #[test]
+    fn parse_policies() {
+        assert_eq!(Ok(LatenessPolicy::Days), "days".parse());
+        assert_eq!(Ok(LatenessPolicy::Hours), "hours".parse());
+        assert_eq!(Ok(LatenessPolicy::FractionalDays(4)), "days/4".parse());
+        assert_eq!(Ok(LatenessPolicy::PercentPerHour(2.5)), "2.5%/hour".parse());
+        assert!("weeks".parse::<LatenessPolicy>().is_err());
+        assert!("days/7".parse::<LatenessPolicy>().is_err());
+        assert!("days/0".parse::<LatenessPolicy>().is_err());
+        assert!("0%/hour".parse::<LatenessPolicy>().is_err());
+    }
+
+    #[test]
+    fn policies_display_as_they_are_written() {
+        for policy in ["days", "hours", "days/2", "10%/hour", "2.5%/hour"] {
+            assert_eq!(
+                policy,
+                policy.parse::<LatenessPolicy>().unwrap().to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::Error;
+
+// One line of the ledger: designation,student,days. The ledger covers a whole term, so that
+// the late days each student has used can be added up across assessments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub designation: String,
+    pub student: String,
+    pub days: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Balance {
+    pub student: String,
+    pub used: f64,
+    pub remaining: f64,
+}
+
+impl Balance {
+    pub fn is_over(&self) -> bool {
+        self.remaining < 0.0
+    }
+}
+
+// A missing ledger is just an empty one; it gets created by the first run.
+pub fn read_ledger(filename: &str) -> Result<Vec<LedgerEntry>, Error> {
+    if !Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(filename)
+        .map_err(|e| Error::io(format!("Unable to read ledger {filename}"), e))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            match fields[..] {
+                [designation, student, days] => Ok(LedgerEntry {
+                    designation: String::from(designation),
+                    student: String::from(student),
+                    days: days.parse().map_err(|_| {
+                        Error::BadArgs(format!(
+                            "Line {} of {filename}: {days} is not a number",
+                            i + 1
+                        ))
+                    })?,
+                }),
+                _ => Err(Error::BadArgs(format!(
+                    "Line {} of {filename} should be <designation>,<student>,<days>",
+                    i + 1
+                ))),
+            }
+        })
+        .collect()
+}
+
+// Replaces whatever the ledger had for this designation, so rerunning an assessment after
+// fixing something doesn't charge anyone twice. Students whose projects couldn't be checked this
+// time keep what they had, rather than losing charges from an earlier run.
+pub fn update_ledger(
+    filename: &str,
+    designation: &str,
+    charges: &[(String, f64)],
+    unchecked: &[String],
+) -> Result<(), Error> {
+    let mut entries: Vec<LedgerEntry> = read_ledger(filename)?
+        .into_iter()
+        .filter(|entry| entry.designation != designation || unchecked.contains(&entry.student))
+        .collect();
+    entries.extend(charges.iter().map(|(student, days)| LedgerEntry {
+        designation: String::from(designation),
+        student: student.clone(),
+        days: *days,
+    }));
+
+    let write_error = |e| Error::io(format!("Unable to write ledger {filename}"), e);
+    let mut ledger_file = File::create(filename).map_err(write_error)?;
+    for entry in entries {
+        let ledger_line = format!("{},{},{}\n", entry.designation, entry.student, entry.days);
+        ledger_file
+            .write_all(ledger_line.as_bytes())
+            .map_err(write_error)?;
+    }
+    Ok(())
+}
+
+pub fn calculate_balances(entries: &[LedgerEntry], allowance: f64) -> Vec<Balance> {
+    let mut used: BTreeMap<&String, f64> = BTreeMap::new();
+    for entry in entries {
+        *used.entry(&entry.student).or_default() += entry.days;
+    }
+    used.into_iter()
+        .map(|(student, used)| Balance {
+            student: student.clone(),
+            used,
+            remaining: allowance - used,
+        })
+        .collect()
+}
+
+pub fn print_balances(balances: &[Balance], allowance: f64) {
+    println!("student,used,remaining");
+    for balance in balances {
+        println!("{},{},{}", balance.student, balance.used, balance.remaining);
+    }
+    for balance in balances.iter().filter(|balance| balance.is_over()) {
+        eprintln!(
+            "{} has used {} late day(s), {} over the allowance of {allowance}.",
+            balance.student, balance.used, -balance.remaining
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::remove_file;
+
+    use crate::ledger::{calculate_balances, read_ledger, update_ledger, Balance, LedgerEntry};
+
+    fn entry(designation: &str, student: &str, days: f64) -> LedgerEntry {
+        LedgerEntry {
+            designation: String::from(designation),
+            student: String::from(student),
+            days,
+        }
+    }
+
+    #[test]
+    fn can_read_ledger() {
+        let entries = read_ledger("test/resources/ledger.csv").unwrap();
+
+        assert_eq!(
+            vec![
+                entry("a1", "username", 1.0),
+                entry("a1", "u2sernam", 0.0),
+                entry("a2", "username", 2.0),
+                entry("a2", "u2sernam", 3.0),
+                entry("a3", "u2sernam", 3.0),
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn missing_ledger_is_empty() {
+        assert!(read_ledger("test/resources/missing-ledger.csv")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn ledger_lines_need_three_fields() {
+        let error = read_ledger("test/resources/sections.csv").unwrap_err();
+
+        assert_eq!(2, error.exit_code());
+        assert_eq!(
+            "Line 1 of test/resources/sections.csv should be <designation>,<student>,<days>",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn update_replaces_the_designation() {
+        let filename = "ledger-test.csv";
+        fs::copy("test/resources/ledger.csv", filename).unwrap();
+
+        update_ledger(filename, "a2", &[(String::from("username"), 1.5)], &[]).unwrap();
+
+        let entries = read_ledger(filename).unwrap();
+        remove_file(filename).unwrap();
+        assert_eq!(
+            vec![
+                entry("a1", "username", 1.0),
+                entry("a1", "u2sernam", 0.0),
+                entry("a3", "u2sernam", 3.0),
+                entry("a2", "username", 1.5),
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn update_keeps_unchecked_students() {
+        let filename = "ledger-test-unchecked.csv";
+        fs::copy("test/resources/ledger.csv", filename).unwrap();
+
+        update_ledger(
+            filename,
+            "a2",
+            &[(String::from("username"), 1.5)],
+            &[String::from("u2sernam")],
+        )
+        .unwrap();
+
+        let entries = read_ledger(filename).unwrap();
+        remove_file(filename).unwrap();
+        assert_eq!(
+            vec![
+                entry("a1", "username", 1.0),
+                entry("a1", "u2sernam", 0.0),
+                entry("a2", "u2sernam", 3.0),
+                entry("a3", "u2sernam", 3.0),
+                entry("a2", "username", 1.5),
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn balances_add_up_across_assessments() {
+        let entries = read_ledger("test/resources/ledger.csv").unwrap();
+
+        let balances = calculate_balances(&entries, 5.0);
+
+        assert_eq!(
+            vec![
+                Balance {
+                    student: String::from("u2sernam"),
+                    used: 6.0,
+                    remaining: -1.0,
+                },
+                Balance {
+                    student: String::from("username"),
+                    used: 3.0,
+                    remaining: 2.0,
+                },
+            ],
+            balances
+        );
+        assert!(balances[0].is_over());
+        assert!(!balances[1].is_over());
+    }
+}
@@ -1,15 +1,49 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines, Write};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use std::{env, fs};
+use std::{env, fmt, fs, process, thread};
 
-use chrono::{DateTime, FixedOffset, NaiveDateTime};
-use chrono_tz::Canada::Eastern;
+use chrono::{DateTime, FixedOffset, Utc};
 use chrono_tz::Tz;
-use gitlab::api::projects::repository::branches::BranchBuilder;
-use gitlab::api::{projects, Query};
+use gitlab::api::projects::repository::branches::{BranchBuilder, Branches};
+use gitlab::api::projects::repository::commits::Commits;
+use gitlab::api::projects::repository::tags::Tags;
+use gitlab::api::{paged, projects, Pagination, Query};
 use gitlab::{Gitlab, ObjectId};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{Cli, Command};
+use crate::config_file::{
+    load_config_file, resolve_balance_settings, resolve_run_settings, ConfigFile, RunSettings,
+};
+use crate::discover::discover_repos;
+use crate::due_date::DueDate;
+use crate::endpoints::{CommitDiff, Compare, ProjectEvents};
+use crate::error::Error;
+use crate::lateness::LatenessPolicy;
+use crate::ledger::{calculate_balances, print_balances, read_ledger, update_ledger};
+use crate::paths::PathFilter;
+use crate::plan::dry_run;
+use crate::report::{write_report, IgnoredCommit, ReportEntry, ReportFormat, Status};
+use crate::roster::load_roster;
+use crate::sections::parse_sections_file;
+
+mod cli;
+mod config_file;
+mod discover;
+mod due_date;
+mod endpoints;
+mod error;
+mod lateness;
+mod ledger;
+mod paths;
+mod plan;
+mod report;
+mod roster;
+mod sections;
 
 const UW_GITLAB_URL: &str = "git.uwaterloo.ca";
 const DEFAULT_BRANCH_NAME: &str = "main";
@@ -19,214 +53,1347 @@ const MINS_PER_DAY: f64 = 60.0 * 24.0;
 #[derive(Debug, Deserialize)]
 struct Project {
     id: u64,
+    // Missing for a project with no commits at all.
+    default_branch: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Commit {
     id: ObjectId,
+    author_name: String,
+    author_email: String,
     committed_date: DateTime<FixedOffset>,
+    parent_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diff {
+    old_path: String,
+    new_path: String,
+}
+
+// Commits come oldest first.
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    commits: Vec<Commit>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Branch {
-    default: bool,
+    name: String,
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
     commit: Commit,
+    // Only annotated tags have one.
+    created_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushData {
+    commit_to: Option<String>,
+    #[serde(rename = "ref")]
+    ref_name: Option<String>,
+    // branch or tag
+    ref_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    created_at: DateTime<FixedOffset>,
+    author_username: Option<String>,
+    push_data: Option<PushData>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum TimestampSource {
+    /// The committed_date inside the commit, which the student's machine controls
+    Commit,
+    /// The time GitLab received the push that made the commit the tip of the branch
+    Push,
+    // When an annotated tag was created. It's only ever recorded, never asked for.
+    #[value(skip)]
+    #[serde(skip_deserializing)]
+    Tag,
+}
+
+impl Display for TimestampSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampSource::Commit => write!(f, "commit"),
+            TimestampSource::Push => write!(f, "push"),
+            TimestampSource::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Submission {
+    project_id: u64,
+    branch: String,
+    commit_id: String,
+    timestamp: DateTime<Tz>,
+    source: TimestampSource,
+    // Later commits that were passed over, newest first.
+    ignored: Vec<IgnoredCommit>,
+    // Set when the submission was found somewhere other than the default branch.
+    warning: Option<String>,
+}
+
+// What a project turned out to have: something to grade, nothing since the starter code, or (in
+// tag mode) no submission tag at all.
+#[derive(Debug)]
+enum LastCommit {
+    Submitted(Submission),
+    NoChange,
+    NoSubmission,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Extension {
+    DueDate(DateTime<Tz>),
+    Extra(Duration),
+}
+
+// One repo to check: {group}-{designation}-{label}, and the students to charge for it.
+#[derive(Debug, Clone, PartialEq)]
+struct Repo {
+    label: String,
+    members: Vec<String>,
+}
+
+impl Repo {
+    // The naming rule from create-project-repos: a line with one student is that student's repo,
+    // anything else is group g{line number}.
+    fn new(line: usize, members: Vec<String>) -> Repo {
+        let label = if members.len() == 1 {
+            members[0].clone()
+        } else {
+            format!("g{line}")
+        };
+        Repo { label, members }
+    }
 }
 
 struct GitLabConfig {
     designation: String,
-    starter_commit_hash: String,
+    // The original starter commit, and any updates to it pushed since.
+    starter_commits: Vec<String>,
+    // Whether everything the starter commits can reach counts as starter code too.
+    starter_ancestry: bool,
     group_name: String,
+    // Local due dates were read in it, and local times are reported in it.
+    timezone: Tz,
     due_date_time: DateTime<Tz>,
     tolerance: Duration,
+    timestamp_source: TimestampSource,
+    extensions: HashMap<String, Extension>,
+    // Which section each student or group is in, and the due dates of the sections that don't
+    // use due_date_time.
+    sections: HashMap<String, String>,
+    section_due_dates: HashMap<String, DateTime<Tz>>,
+    // None means each project's own default branch.
+    branch: Option<String>,
+    // Set in tag mode, which ignores branches altogether.
+    tag: Option<glob::Pattern>,
+    // Consider every branch, not just the default (or configured) one.
+    all_branches: bool,
+    jobs: usize,
+    report: Option<ReportFormat>,
+    ledger: Option<String>,
+    lateness: LatenessPolicy,
+    // Anything later than this, in the policy's units, is past what late days can cover.
+    max_lateness: Option<f64>,
+    // Usernames or emails of TAs, bots and the like, whose commits aren't submissions.
+    staff: Vec<String>,
+    graded_paths: PathFilter,
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if !validate_args_len(&args) {
-        return;
+    if let Err(e) = run(cli::parse_args(env::args().collect())) {
+        eprintln!("{e}");
+        process::exit(e.exit_code());
     }
+}
 
-    let config = build_config(&args);
-    let repo_members = parse_csv_file(args.get(6).unwrap());
-
-    let token = read_token_file(args.get(7).unwrap());
-    let client = Gitlab::new(String::from(UW_GITLAB_URL), token).unwrap();
+// Projects that can't be checked don't stop the others; once they're all done, the first one in
+// roster order decides the exit code.
+fn run(cli: Cli) -> Result<(), Error> {
+    let config_file = load_config_file(cli.config.as_ref())?;
+    let (host, insecure) = resolve_host(&cli, &config_file);
+
+    match cli.command {
+        Command::Run(run_args) => {
+            let settings = resolve_run_settings(&run_args, &config_file)?;
+            let config = build_config(&settings)?;
+            let roster = settings
+                .roster
+                .as_ref()
+                .map(|roster| load_roster(roster, settings.roster_header, settings.strict))
+                .transpose()?;
+            let token = read_token_file(&settings.token_file)?;
+            let client = connect(host, insecure, token)?;
+
+            let repos = match roster {
+                Some(repos) => repos,
+                None => discover_repos(&client, &config)?,
+            };
+
+            if run_args.dry_run {
+                let problems = dry_run(&client, &config, &repos);
+                let summary = format!("{} problem(s) found; nothing was written.", problems.len());
+                return Error::first_of(problems, summary);
+            }
+            let failures = get_late_days(client, repos, config)?;
+            let summary = format!(
+                "{} project(s) could not be checked; see the errors file.",
+                failures.len()
+            );
+            Error::first_of(failures, summary)?;
+        }
+        Command::Balance(balance_args) => {
+            let (ledger, allowance) = resolve_balance_settings(&balance_args, &config_file)?;
+            let balances = calculate_balances(&read_ledger(&ledger)?, allowance);
+            print_balances(&balances, allowance);
+        }
+    }
+    Ok(())
+}
 
-    get_late_days(client, repo_members, config)
+// Command line (or environment variable), then the config file, then UW's GitLab.
+fn resolve_host(cli: &Cli, config_file: &ConfigFile) -> (String, bool) {
+    let host = cli
+        .host
+        .clone()
+        .or_else(|| config_file.gitlab.host.clone())
+        .unwrap_or_else(|| String::from(UW_GITLAB_URL));
+    (host, cli.insecure || config_file.gitlab.insecure)
 }
 
-fn validate_args_len(args: &Vec<String>) -> bool {
-    if args.len() != 8 {
-        println!(
-            "Usage: {} <designation> <starter_commit_hash> <gitlab_group_name> <due_date_time> <tolerance_in_mins> <list_of_student_groups.csv> <token_file>",
-            args.first().unwrap()
-        );
-        println!(
-            "Example: {} a1 c335fdb690e88c7cd162e10d42800e93 ece459-1231 \"2023-01-27 23:59\" 60 students.csv token.git",
-            args.first().unwrap()
-        );
-        return false;
-    }
-    true
+// Connecting checks the token, so this is where a bad or expired one shows up.
+fn connect(host: String, insecure: bool, token: String) -> Result<Gitlab, Error> {
+    let client = if insecure {
+        println!("Connecting to {host} over plain HTTP; don't do this with a real token!");
+        Gitlab::new_insecure(&host, token)
+    } else {
+        Gitlab::new(&host, token)
+    };
+    client.map_err(|e| Error::Auth(format!("Unable to log in to {host}: {e}")))
 }
 
-fn build_config(args: &[String]) -> GitLabConfig {
-    let duration_minutes: u64 = args.get(5).unwrap().parse().unwrap();
-    let naive_date_time =
-        NaiveDateTime::parse_from_str(args.get(4).unwrap(), DATE_TIME_FORMAT).unwrap();
-    let due_date = naive_date_time.and_local_timezone(Eastern).unwrap();
+fn build_config(settings: &RunSettings) -> Result<GitLabConfig, Error> {
+    let extensions = match &settings.extensions {
+        Some(filename) => parse_extensions_file(filename, settings.timezone)?,
+        None => HashMap::new(),
+    };
+    let sections = match &settings.sections {
+        Some(filename) => parse_sections_file(filename)?,
+        None if settings.section_due.is_empty() => HashMap::new(),
+        None => {
+            return Err(Error::BadArgs(String::from(
+                "Section due dates need a sections file; pass --sections or set sections in the config file",
+            )))
+        }
+    };
+    // A typo in a section name would otherwise quietly give that section the regular due date.
+    for section in settings.section_due.keys() {
+        if !sections.values().any(|s| s == section) {
+            return Err(Error::BadArgs(format!(
+                "Section {section} has a due date, but nobody is in it"
+            )));
+        }
+    }
+    let tag = settings
+        .tag
+        .as_ref()
+        .map(|tag| {
+            glob::Pattern::new(tag)
+                .map_err(|e| Error::BadArgs(format!("Invalid tag pattern {tag}: {e}")))
+        })
+        .transpose()?;
+    if let Some(max) = settings.max_lateness {
+        if !(max.is_finite() && max >= 0.0) {
+            return Err(Error::BadArgs(format!(
+                "Invalid maximum lateness {max}; use a number that's at least 0"
+            )));
+        }
+    }
+    if settings.ledger.is_some() && !settings.lateness.counts_days() {
+        return Err(Error::BadArgs(format!(
+            "The ledger counts days, so it can't be used with lateness policy {}",
+            settings.lateness
+        )));
+    }
+    let tolerance = settings
+        .tolerance
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| {
+            Error::BadArgs(format!(
+                "Tolerance of {} minutes is too long",
+                settings.tolerance
+            ))
+        })?;
 
     let config = GitLabConfig {
-        designation: String::from(args.get(1).unwrap()),
-        starter_commit_hash: String::from(args.get(2).unwrap()),
-        group_name: String::from(args.get(3).unwrap()),
-        due_date_time: due_date,
-        tolerance: Duration::from_secs(60 * duration_minutes),
+        designation: settings.designation.clone(),
+        starter_commits: settings.starter_commits.clone(),
+        starter_ancestry: settings.starter_ancestry,
+        group_name: settings.group.clone(),
+        timezone: settings.timezone,
+        due_date_time: settings.due,
+        tolerance,
+        timestamp_source: settings.timestamp_source,
+        extensions,
+        sections,
+        section_due_dates: settings.section_due.clone().into_iter().collect(),
+        branch: settings.branch.clone(),
+        tag,
+        all_branches: settings.all_branches,
+        jobs: settings.jobs,
+        report: settings.report,
+        ledger: settings.ledger.clone(),
+        lateness: settings.lateness,
+        max_lateness: settings.max_lateness,
+        staff: settings.staff.clone(),
+        graded_paths: PathFilter::new(&settings.include, &settings.exclude)?,
     };
-    config
+    check_due_dates(&config)?;
+    Ok(config)
 }
 
-fn get_late_days(client: Gitlab, repo_members: Vec<Vec<String>>, config: GitLabConfig) {
+// Returns why each project that could not be checked couldn't be; those are listed in the errors
+// file too.
+fn get_late_days(
+    client: Gitlab,
+    repos: Vec<Repo>,
+    config: GitLabConfig,
+) -> Result<Vec<Error>, Error> {
     let output_file_name = format! {"{}-{}-latedays.csv", config.group_name, config.designation};
     let no_change_file_name = format! {"{}-{}-nochange.csv", config.group_name, config.designation};
-    let mut output_file = File::create(output_file_name).unwrap();
-    let mut no_change_file = File::create(no_change_file_name).unwrap();
-    let effective_due_date = calculate_effective_due_date(config.due_date_time, config.tolerance);
+    let timestamps_file_name =
+        format! {"{}-{}-timestamps.csv", config.group_name, config.designation};
+    let errors_file_name = format! {"{}-{}-errors.csv", config.group_name, config.designation};
+    let exceeded_file_name = format! {"{}-{}-exceeded.csv", config.group_name, config.designation};
+    let no_submission_file_name =
+        format! {"{}-{}-nosubmission.csv", config.group_name, config.designation};
+    let mut output_file = OutputFile::create(output_file_name)?;
+    let mut no_change_file = OutputFile::create(no_change_file_name)?;
+    let mut timestamps_file = OutputFile::create(timestamps_file_name)?;
+    let mut errors_file = OutputFile::create(errors_file_name)?;
+    let mut exceeded_file = OutputFile::create(exceeded_file_name)?;
+    let mut no_submission_file = OutputFile::create(no_submission_file_name)?;
+    let mut failures = Vec::new();
+    let mut report = Vec::new();
+    let mut charges = Vec::new();
+    let mut unchecked = Vec::new();
+
+    let project_names = get_project_names(&config, &repos);
+    let last_commits = get_last_commits(&client, &config, &project_names);
+
+    for (i, last_commit) in last_commits.into_iter().enumerate() {
+        let group_or_student = &repos.get(i).unwrap().members;
+        let project_label = &repos.get(i).unwrap().label;
+        let project_name = project_names.get(i).unwrap();
+        let project_path = format!("{}/{project_name}", config.group_name);
+        let last_commit = match last_commit {
+            Ok(last_commit) => last_commit,
+            Err(failure) => {
+                let reason = failure.to_string();
+                println!("Project {project_name} could not be checked: {reason}");
+                let error_line = format!("{project_name},{}\n", csv_field(&reason));
+                errors_file.write_line(&error_line)?;
+                failures.push(failure);
+                unchecked.extend(group_or_student.iter().cloned());
+                let mut entry =
+                    ReportEntry::new(project_path, group_or_student.clone(), Status::Error);
+                entry.error = Some(reason);
+                report.push(entry);
+                continue;
+            }
+        };
+        let submission = match last_commit {
+            LastCommit::Submitted(submission) => submission,
+            LastCommit::NoChange => {
+                println!(
+                    "Project {project_name} has not been changed since the starter commit hash."
+                );
+                for student in group_or_student {
+                    let no_change_line = format!("{student}\n");
+                    no_change_file.write_line(&no_change_line)?;
+                }
+                report.push(ReportEntry::new(
+                    project_path,
+                    group_or_student.clone(),
+                    Status::NoChange,
+                ));
+                continue;
+            }
+            LastCommit::NoSubmission => {
+                println!("Project {project_name} has no submission tag.");
+                for student in group_or_student {
+                    let no_submission_line = format!("{student}\n");
+                    no_submission_file.write_line(&no_submission_line)?;
+                }
+                report.push(ReportEntry::new(
+                    project_path,
+                    group_or_student.clone(),
+                    Status::NoSubmission,
+                ));
+                continue;
+            }
+        };
+        let (effective_due_date, extension_used, section) =
+            calculate_project_due_date(&config, project_label, group_or_student)?;
+        if let Some(key) = &extension_used {
+            println!(
+                "Project {project_name} has an extension for {key}; effective due date is {effective_due_date}."
+            );
+        }
+        let timestamp_line = format!(
+            "{project_name},{},{},{},{},{},{},{}\n",
+            submission.commit_id,
+            submission.timestamp.to_rfc3339(),
+            submission.source,
+            effective_due_date.to_rfc3339(),
+            extension_used.clone().unwrap_or_default(),
+            submission.branch,
+            section.clone().unwrap_or_default()
+        );
+        timestamps_file.write_line(&timestamp_line)?;
+
+        let lateness =
+            calculate_lateness(submission.timestamp, effective_due_date, config.lateness);
+        let status = if config.max_lateness.is_some_and(|max| lateness > max) {
+            println!(
+                "Project {project_name} would be charged {lateness} {}, more than the maximum allowed.",
+                config.lateness.unit()
+            );
+            for student in group_or_student {
+                let exceeded_line = format!("{student}\n");
+                exceeded_file.write_line(&exceeded_line)?;
+            }
+            Status::Exceeded
+        } else {
+            println!(
+                "Project {project_name} is charged {lateness} {}.",
+                config.lateness.unit()
+            );
+            for student in group_or_student {
+                let file_line = format!("{student},{lateness}\n");
+                output_file.write_line(&file_line)?;
+                charges.push((student.clone(), lateness));
+            }
+            if lateness > 0.0 {
+                Status::Late
+            } else {
+                Status::OnTime
+            }
+        };
+        let mut entry = ReportEntry::new(project_path, group_or_student.clone(), status);
+        entry.project_id = Some(submission.project_id);
+        entry.branch = Some(submission.branch);
+        entry.commit = Some(submission.commit_id);
+        entry.timestamp_utc = Some(submission.timestamp.with_timezone(&Utc).to_rfc3339());
+        entry.timestamp_local = Some(submission.timestamp.to_rfc3339());
+        entry.timestamp_source = Some(submission.source);
+        entry.effective_due = Some(effective_due_date.to_rfc3339());
+        entry.extension = extension_used;
+        entry.section = section;
+        entry.minutes_late = Some(
+            (submission.timestamp - effective_due_date)
+                .num_minutes()
+                .max(0),
+        );
+        entry.late_days = Some(lateness);
+        entry.ignored_commits = submission.ignored;
+        entry.warning = submission.warning;
+        report.push(entry);
+    }
+
+    if let Some(format) = config.report {
+        let report_file_name = format!(
+            "{}-{}-report.{}",
+            config.group_name,
+            config.designation,
+            format.extension()
+        );
+        write_report(&report_file_name, format, &report)?;
+    }
+    if let Some(ledger) = &config.ledger {
+        if !unchecked.is_empty() {
+            println!(
+                "Keeping the {} ledger entries of {} student(s) whose projects could not be checked.",
+                config.designation,
+                unchecked.len()
+            );
+        }
+        update_ledger(ledger, &config.designation, &charges, &unchecked)?;
+    }
+    Ok(failures)
+}
 
-    for i in 0..repo_members.len() {
-        let group_or_student = repo_members.get(i).unwrap();
-        let project_name = if group_or_student.len() == 1 {
+// One of the CSV files a run writes, named so that a failed write can say which.
+struct OutputFile {
+    name: String,
+    file: File,
+}
+
+impl OutputFile {
+    fn create(name: String) -> Result<OutputFile, Error> {
+        let file =
+            File::create(&name).map_err(|e| Error::io(format!("Unable to create {name}"), e))?;
+        Ok(OutputFile { name, file })
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), Error> {
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(|e| Error::io(format!("Unable to write {}", self.name), e))
+    }
+}
+
+// Error messages from GitLab can contain commas and quotes of their own.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+fn get_project_names(config: &GitLabConfig, repos: &[Repo]) -> Vec<String> {
+    repos
+        .iter()
+        .map(|repo| {
             format!(
                 "{}-{}-{}",
-                config.group_name,
-                config.designation,
-                group_or_student.first().unwrap()
+                config.group_name, config.designation, repo.label
             )
-        } else {
-            format!("{}-{}-g{}", config.group_name, config.designation, (i + 1))
-        };
+        })
+        .collect()
+}
 
+fn get_last_commits(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_names: &[String],
+) -> Vec<Result<LastCommit, Error>> {
+    for_each_project(config.jobs, project_names, |project_name| {
         println!("Calculating late days for project {project_name}...");
-        let last_commit = get_last_commit(
-            &client,
-            &config.group_name,
-            &config.starter_commit_hash,
-            &project_name,
-        );
-        if last_commit.is_none() {
-            println!("Project {project_name} has not been changed since the starter commit hash.");
-            for student in group_or_student {
-                let no_change_line = format!("{student}\n");
-                no_change_file.write_all(no_change_line.as_bytes()).unwrap();
-            }
-            continue;
+        get_last_commit(client, config, project_name)
+    })
+}
+
+// Spreads the projects over that many worker threads. The results come back in the same order
+// as project_names, however the work was interleaved, so the output files stay in roster order.
+fn for_each_project<T: Send>(
+    jobs: usize,
+    project_names: &[String],
+    check: impl Fn(&String) -> T + Sync,
+) -> Vec<T> {
+    let jobs = jobs.clamp(1, project_names.len().max(1));
+    let next_project = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next_project.fetch_add(1, Ordering::Relaxed);
+                        let Some(project_name) = project_names.get(i) else {
+                            break;
+                        };
+                        done.push((i, check(project_name)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn calculate_effective_due_date(
+    due_date_time: DateTime<Tz>,
+    tolerance: Duration,
+) -> Result<DateTime<Tz>, Error> {
+    chrono::Duration::from_std(tolerance)
+        .ok()
+        .and_then(|tolerance| due_date_time.checked_add_signed(tolerance))
+        .ok_or_else(|| {
+            Error::BadArgs(format!(
+                "{}s after {due_date_time} is too far in the future",
+                tolerance.as_secs()
+            ))
+        })
+}
+
+// Every due date a project could end up with, so that an extension or tolerance too large to add
+// is caught before any output file is written.
+fn check_due_dates(config: &GitLabConfig) -> Result<(), Error> {
+    let due_dates = std::iter::once(&config.due_date_time).chain(config.section_due_dates.values());
+    for due_date_time in due_dates {
+        calculate_effective_due_date(*due_date_time, config.tolerance)?;
+        for extension in config.extensions.values() {
+            let extended_due_date = match extension {
+                Extension::DueDate(due_date) => *due_date,
+                Extension::Extra(extra) => calculate_effective_due_date(*due_date_time, *extra)?,
+            };
+            calculate_effective_due_date(extended_due_date, config.tolerance)?;
         }
-        let lateness_in_days = calculate_lateness(last_commit.unwrap(), effective_due_date);
-        println!("Project {project_name} is submitted {lateness_in_days} day(s) late.");
-        for student in group_or_student {
-            let file_line = format!("{student},{lateness_in_days}\n");
-            output_file.write_all(file_line.as_bytes()).unwrap();
+    }
+    Ok(())
+}
+
+// The effective due date, and the extension and section that went into it, if any.
+type ProjectDueDate = (DateTime<Tz>, Option<String>, Option<String>);
+
+// The most generous extension granted to the project label (username or g<N>) or any member wins.
+// Extra time is added to the section's due date.
+fn calculate_project_due_date(
+    config: &GitLabConfig,
+    project_label: &String,
+    members: &[String],
+) -> Result<ProjectDueDate, Error> {
+    let (section_due_date, section) = calculate_section_due_date(config, project_label, members);
+    let mut due_date_time = section_due_date;
+    let mut extension_used = None;
+
+    for key in std::iter::once(project_label).chain(members) {
+        let extended_due_date = match config.extensions.get(key) {
+            Some(Extension::DueDate(due_date)) => *due_date,
+            Some(Extension::Extra(extra)) => {
+                calculate_effective_due_date(section_due_date, *extra)?
+            }
+            None => continue,
+        };
+        if extended_due_date > due_date_time {
+            due_date_time = extended_due_date;
+            extension_used = Some(key.clone());
         }
     }
+    Ok((
+        calculate_effective_due_date(due_date_time, config.tolerance)?,
+        extension_used,
+        section,
+    ))
 }
 
-fn calculate_effective_due_date(due_date_time: DateTime<Tz>, tolerance: Duration) -> DateTime<Tz> {
-    due_date_time
-        .checked_add_signed(chrono::Duration::from_std(tolerance).unwrap())
-        .unwrap()
+// The due date of the section the project label or its members are in. A group whose members
+// are in different sections gets the latest of their due dates.
+fn calculate_section_due_date(
+    config: &GitLabConfig,
+    project_label: &String,
+    members: &[String],
+) -> (DateTime<Tz>, Option<String>) {
+    let mut due_date_time = None;
+    let mut section_used = None;
+
+    for key in std::iter::once(project_label).chain(members) {
+        let Some(section) = config.sections.get(key) else {
+            continue;
+        };
+        let section_due_date = config
+            .section_due_dates
+            .get(section)
+            .copied()
+            .unwrap_or(config.due_date_time);
+        if due_date_time.is_none_or(|due_date_time| section_due_date > due_date_time) {
+            due_date_time = Some(section_due_date);
+            section_used = Some(section.clone());
+        }
+    }
+    (due_date_time.unwrap_or(config.due_date_time), section_used)
 }
 
-fn calculate_lateness(last_commit: DateTime<Tz>, due_date_time: DateTime<Tz>) -> i64 {
+fn calculate_lateness(
+    last_commit: DateTime<Tz>,
+    due_date_time: DateTime<Tz>,
+    policy: LatenessPolicy,
+) -> f64 {
     if last_commit.le(&due_date_time) {
-        return 0;
+        return 0.0;
     }
-    let diff = (last_commit - due_date_time).num_minutes();
-    1 + (diff as f64 / MINS_PER_DAY).floor() as i64
+    policy.charge(last_commit - due_date_time)
 }
 
 fn get_last_commit(
     client: &Gitlab,
-    group_name: &String,
-    starter_commit_hash: &String,
+    config: &GitLabConfig,
     project_name: &String,
-) -> Option<DateTime<Tz>> {
+) -> Result<LastCommit, Error> {
+    let group_name = &config.group_name;
     let project_builder = projects::ProjectBuilder::default()
         .project(format!("{group_name}/{project_name}"))
         .build()
         .unwrap();
 
-    let project: Project = project_builder.query(client).unwrap();
+    let project: Project = project_builder
+        .query(client)
+        .map_err(|e| Error::lookup(e, "project", Error::ProjectNotFound))?;
     let project_id = project.id;
+    if let Some(tag_pattern) = &config.tag {
+        return get_tagged_commit(client, config, project_id, project_name, tag_pattern);
+    }
+    let default_branch = project
+        .default_branch
+        .unwrap_or_else(|| String::from(DEFAULT_BRANCH_NAME));
+
+    let (branch_name, commit, ignored) = if config.all_branches {
+        match get_latest_branch_commit(client, config, project_id, project_name)? {
+            Some(found) => found,
+            None => return Ok(LastCommit::NoChange),
+        }
+    } else {
+        let branch_name = match &config.branch {
+            Some(branch_name) => {
+                if branch_name != &default_branch {
+                    println!(
+                        "Project {project_name} has default branch {default_branch}; evaluating {branch_name} as configured."
+                    );
+                }
+                branch_name
+            }
+            None => &default_branch,
+        };
+
+        let branch_builder = BranchBuilder::default()
+            .project(project_id)
+            .branch(branch_name.as_str())
+            .build()
+            .unwrap();
+
+        let branch: Branch = branch_builder.query(client).map_err(|e| {
+            Error::lookup(e, &format!("branch {branch_name}"), |reason| {
+                Error::BranchNotFound {
+                    branch: branch_name.clone(),
+                    reason,
+                }
+            })
+        })?;
+        match get_branch_commit(
+            client,
+            config,
+            project_id,
+            project_name,
+            branch_name,
+            branch.commit,
+        )? {
+            Some((commit, ignored)) => (branch_name.clone(), commit, ignored),
+            None => return Ok(LastCommit::NoChange),
+        }
+    };
+    let warning = if config.all_branches && branch_name != default_branch {
+        let warning =
+            format!("submission is on {branch_name}, not the default branch {default_branch}");
+        println!("Project {project_name}: {warning}.");
+        Some(warning)
+    } else {
+        None
+    };
+
+    let commit_id = commit.id.value().clone();
+    if config.timestamp_source == TimestampSource::Push {
+        if let Some(pushed_at) =
+            get_push_time(client, project_id, &branch_name, &commit_id, &config.staff)?
+        {
+            return Ok(LastCommit::Submitted(Submission {
+                project_id,
+                branch: branch_name,
+                commit_id,
+                timestamp: pushed_at.with_timezone(&config.timezone),
+                source: TimestampSource::Push,
+                ignored,
+                warning,
+            }));
+        }
+        println!(
+            "Project {project_name} has no push event for commit {commit_id}; falling back to its committed date."
+        );
+    }
+    Ok(LastCommit::Submitted(Submission {
+        project_id,
+        branch: branch_name,
+        commit_id,
+        timestamp: commit.committed_date.with_timezone(&config.timezone),
+        source: TimestampSource::Commit,
+        ignored,
+        warning,
+    }))
+}
+
+// The last commit on the branch that counts as a submission, and the ones passed over to get
+// to it. None if there isn't one since the starter code.
+fn get_branch_commit(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    project_name: &str,
+    branch_name: &str,
+    tip: Commit,
+) -> Result<Option<(Commit, Vec<IgnoredCommit>)>, Error> {
+    if config.starter_ancestry {
+        let mut ignored = Vec::new();
+        for commit in get_commits_since_starter(client, config, project_id, branch_name)? {
+            match ignore_reason(client, config, project_id, &commit)? {
+                None => return Ok(Some((commit, ignored))),
+                Some(reason) => ignored.push(ignore_commit(config, project_name, &commit, reason)),
+            }
+        }
+        return Ok(None);
+    }
+    if is_starter_commit(config, &tip) {
+        return Ok(None);
+    }
+    let mut ignored = Vec::new();
+    let commit = match ignore_reason(client, config, project_id, &tip)? {
+        None => tip,
+        Some(reason) => {
+            ignored.push(ignore_commit(config, project_name, &tip, reason));
+            match get_last_graded_commit(
+                client,
+                config,
+                project_id,
+                project_name,
+                branch_name,
+                &mut ignored,
+            )? {
+                Some(commit) => commit,
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some((commit, ignored)))
+}
+
+// Work left on a feature branch that never got merged still counts: the submission is the
+// latest commit that any branch would give. Commits are compared the way they'll be timed, so
+// with push timestamps a backdated commit can't win or lose on its committed date.
+fn get_latest_branch_commit(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    project_name: &str,
+) -> Result<Option<(String, Commit, Vec<IgnoredCommit>)>, Error> {
+    let branches_endpoint = Branches::builder().project(project_id).build().unwrap();
+    let branches: Vec<Branch> = paged(branches_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, "branches"))?;
+
+    let events = if config.timestamp_source == TimestampSource::Push {
+        Some(get_push_events(client, project_id, &config.staff)?)
+    } else {
+        None
+    };
+    let submitted_at = |branch_name: &String, commit: &Commit| {
+        events
+            .as_ref()
+            .and_then(|events| first_push(events, branch_name, commit.id.value()))
+            .unwrap_or(commit.committed_date)
+    };
+
+    let mut latest: Option<(String, Commit, Vec<IgnoredCommit>, DateTime<FixedOffset>)> = None;
+    for branch in branches {
+        let found = get_branch_commit(
+            client,
+            config,
+            project_id,
+            project_name,
+            &branch.name,
+            branch.commit,
+        )?;
+        if let Some((commit, ignored)) = found {
+            let time = submitted_at(&branch.name, &commit);
+            if latest
+                .as_ref()
+                .is_none_or(|(_, _, _, latest_time)| time > *latest_time)
+            {
+                latest = Some((branch.name, commit, ignored, time));
+            }
+        }
+    }
+    Ok(latest.map(|(branch_name, commit, ignored, _)| (branch_name, commit, ignored)))
+}
+
+// The latest tag matching the pattern is the submission. Its time is when the tag was pushed,
+// or when it was created; a lightweight tag has no time of its own, so that's the commit's.
+fn get_tagged_commit(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    project_name: &str,
+    tag_pattern: &glob::Pattern,
+) -> Result<LastCommit, Error> {
+    let tags_endpoint = Tags::builder().project(project_id).build().unwrap();
+    let tags: Vec<Tag> = paged(tags_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, "tags"))?;
+    let Some(tag) = tags
+        .into_iter()
+        .filter(|tag| tag_pattern.matches(&tag.name))
+        .max_by_key(|tag| tag.created_at.unwrap_or(tag.commit.committed_date))
+    else {
+        return Ok(LastCommit::NoSubmission);
+    };
+    let unchanged = if config.starter_ancestry {
+        get_commits_since_starter(client, config, project_id, &tag.name)?.is_empty()
+    } else {
+        is_starter_commit(config, &tag.commit)
+    };
+    if unchanged {
+        return Ok(LastCommit::NoChange);
+    }
+
+    let commit_id = tag.commit.id.value().clone();
+    let branch = format!("tags/{}", tag.name);
+    if config.timestamp_source == TimestampSource::Push {
+        if let Some(pushed_at) = get_tag_push_time(client, project_id, &tag.name, &config.staff)? {
+            return Ok(LastCommit::Submitted(Submission {
+                project_id,
+                branch,
+                commit_id,
+                timestamp: pushed_at.with_timezone(&config.timezone),
+                source: TimestampSource::Push,
+                ignored: Vec::new(),
+                warning: None,
+            }));
+        }
+        println!(
+            "Project {project_name} has no push event for tag {}; falling back to its creation date.",
+            tag.name
+        );
+    }
+    let (timestamp, source) = match tag.created_at {
+        Some(created_at) => (created_at, TimestampSource::Tag),
+        None => (tag.commit.committed_date, TimestampSource::Commit),
+    };
+    Ok(LastCommit::Submitted(Submission {
+        project_id,
+        branch,
+        commit_id,
+        timestamp: timestamp.with_timezone(&config.timezone),
+        source,
+        ignored: Vec::new(),
+        warning: None,
+    }))
+}
 
-    let branch_builder = BranchBuilder::default()
+// Walks back from the tip of the branch, which has already been ignored, past anything else
+// that doesn't count to the last commit that does. None if there isn't one since the starter
+// code.
+fn get_last_graded_commit(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    project_name: &str,
+    branch_name: &str,
+    ignored: &mut Vec<IgnoredCommit>,
+) -> Result<Option<Commit>, Error> {
+    let commits_endpoint = Commits::builder()
         .project(project_id)
-        .branch(DEFAULT_BRANCH_NAME)
+        .ref_name(branch_name)
         .build()
         .unwrap();
+    let commits = paged(commits_endpoint, Pagination::All);
 
-    let branch: Branch = branch_builder.query(client).unwrap();
-    if !branch.default {
-        println!(
-            "Project {project_name} uses a different default branch than expected {DEFAULT_BRANCH_NAME}!",
-        )
-    }
-    if branch.commit.id.value() == starter_commit_hash {
-        return None;
+    for commit in commits.iter(client).skip(1) {
+        let commit: Commit = commit.map_err(|e| Error::gitlab(e, "commit history"))?;
+        if is_starter_commit(config, &commit) {
+            return Ok(None);
+        }
+        match ignore_reason(client, config, project_id, &commit)? {
+            None => return Ok(Some(commit)),
+            Some(reason) => ignored.push(ignore_commit(config, project_name, &commit, reason)),
+        }
     }
-    Some(branch.commit.committed_date.with_timezone(&Eastern))
+    Ok(None)
 }
 
-fn parse_csv_file(filename: &String) -> Vec<Vec<String>> {
-    let mut result: Vec<Vec<String>> = Vec::new();
-    let lines = read_lines(filename);
+fn is_starter_commit(config: &GitLabConfig, commit: &Commit) -> bool {
+    config.starter_commits.contains(commit.id.value())
+}
 
-    for line in lines {
-        let line = line.unwrap();
-        let mut inner = Vec::new();
-        for user in line.split(',') {
-            inner.push(String::from(user.trim()))
+// Everything on the ref since the original starter commit, the first one given, minus the later
+// starter commits and whatever led up to them; newest first. A ref that is behind the starter
+// has nothing since.
+fn get_commits_since_starter(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    ref_name: &str,
+) -> Result<Vec<Commit>, Error> {
+    let original = &config.starter_commits[0];
+    let compare_endpoint = Compare {
+        project: project_id,
+        from: original,
+        to: ref_name,
+    };
+    let comparison: Comparison = compare_endpoint
+        .query(client)
+        .map_err(|e| Error::gitlab(e, &format!("comparison with starter commit {original}")))?;
+
+    let parents: HashMap<&String, &Vec<String>> = comparison
+        .commits
+        .iter()
+        .map(|commit| (commit.id.value(), &commit.parent_ids))
+        .collect();
+    let mut starter_code: HashSet<&String> = HashSet::new();
+    let mut to_visit: Vec<&String> = config.starter_commits.iter().collect();
+    while let Some(id) = to_visit.pop() {
+        if starter_code.insert(id) {
+            to_visit.extend(
+                parents
+                    .get(id)
+                    .into_iter()
+                    .flat_map(|parent_ids| parent_ids.iter()),
+            );
         }
-        result.push(inner);
     }
-    result
+    let starter_code: HashSet<String> = starter_code.into_iter().cloned().collect();
+
+    Ok(comparison
+        .commits
+        .into_iter()
+        .rev()
+        .filter(|commit| !starter_code.contains(commit.id.value()))
+        .collect())
 }
 
-fn read_lines(filename: &String) -> Lines<BufReader<File>> {
-    if let Ok(file) = File::open(filename) {
-        BufReader::new(file).lines()
+// Why a commit can't be the submission: staff made it, or it only touches ungraded paths.
+fn ignore_reason(
+    client: &Gitlab,
+    config: &GitLabConfig,
+    project_id: u64,
+    commit: &Commit,
+) -> Result<Option<String>, Error> {
+    if is_staff_commit(&config.staff, commit) {
+        return Ok(Some(format!(
+            "committed by staff {} <{}>",
+            commit.author_name, commit.author_email
+        )));
+    }
+    if config.graded_paths.is_empty() {
+        return Ok(None);
+    }
+
+    let diff_endpoint = CommitDiff {
+        project: project_id,
+        commit: commit.id.value(),
+    };
+    let diffs: Vec<Diff> = paged(diff_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, &format!("changes in commit {}", commit.id.value())))?;
+    let graded = diffs.iter().any(|diff| {
+        config.graded_paths.is_graded(&diff.old_path)
+            || config.graded_paths.is_graded(&diff.new_path)
+    });
+    if graded {
+        Ok(None)
     } else {
-        panic!("Failed to read {filename}")
+        Ok(Some(String::from("only changes ungraded paths")))
+    }
+}
+
+fn ignore_commit(
+    config: &GitLabConfig,
+    project_name: &str,
+    commit: &Commit,
+    reason: String,
+) -> IgnoredCommit {
+    println!(
+        "Project {project_name}: ignoring commit {}, {reason}.",
+        commit.id.value()
+    );
+    IgnoredCommit {
+        commit: commit.id.value().clone(),
+        timestamp: commit
+            .committed_date
+            .with_timezone(&config.timezone)
+            .to_rfc3339(),
+        reason,
+    }
+}
+
+// Commits only carry a name and email, so a username matches the author name or the part of
+// the email before the @, which is how UW addresses work.
+fn is_staff_commit(staff: &[String], commit: &Commit) -> bool {
+    let local_part = commit
+        .author_email
+        .split_once('@')
+        .map_or(commit.author_email.as_str(), |(local_part, _)| local_part);
+    is_staff(
+        staff,
+        local_part,
+        Some(&commit.author_name),
+        Some(&commit.author_email),
+    )
+}
+
+// Push events only carry the username, so a staff entry given as an email never matches one.
+fn is_staff_push(staff: &[String], event: &Event) -> bool {
+    event
+        .author_username
+        .as_ref()
+        .is_some_and(|username| is_staff(staff, username, None, None))
+}
+
+// Entries with an @ are emails; anything else is a username, or a name if there is one.
+// Usernames and emails are matched ignoring case, as GitLab does.
+fn is_staff(staff: &[String], username: &str, name: Option<&str>, email: Option<&str>) -> bool {
+    staff.iter().any(|who| {
+        if who.contains('@') {
+            email.is_some_and(|email| who.eq_ignore_ascii_case(email))
+        } else {
+            who.eq_ignore_ascii_case(username) || name == Some(who.as_str())
+        }
+    })
+}
+
+// The earliest push to the branch, by anyone but staff, that left it at the given commit. GitLab
+// only keeps events for a few years, and commits merged through the web UI have no push of
+// their own.
+fn get_push_time(
+    client: &Gitlab,
+    project_id: u64,
+    branch_name: &String,
+    commit_id: &String,
+    staff: &[String],
+) -> Result<Option<DateTime<FixedOffset>>, Error> {
+    Ok(first_push(
+        &get_push_events(client, project_id, staff)?,
+        branch_name,
+        commit_id,
+    ))
+}
+
+fn first_push(
+    events: &[Event],
+    branch_name: &String,
+    commit_id: &String,
+) -> Option<DateTime<FixedOffset>> {
+    events
+        .iter()
+        .filter(|event| {
+            event.push_data.as_ref().is_some_and(|push| {
+                push.ref_type.as_deref() != Some("tag")
+                    && push.commit_to.as_ref() == Some(commit_id)
+                    && push.ref_name.as_ref() == Some(branch_name)
+            })
+        })
+        .map(|event| event.created_at)
+        .min()
+}
+
+// The latest push of the tag by anyone but staff; if a tag was deleted and pushed again, the
+// last push is the one that made it what it is now.
+fn get_tag_push_time(
+    client: &Gitlab,
+    project_id: u64,
+    tag_name: &String,
+    staff: &[String],
+) -> Result<Option<DateTime<FixedOffset>>, Error> {
+    Ok(get_push_events(client, project_id, staff)?
+        .into_iter()
+        .filter(|event| {
+            event.push_data.as_ref().is_some_and(|push| {
+                push.ref_type.as_deref() == Some("tag") && push.ref_name.as_ref() == Some(tag_name)
+            })
+        })
+        .map(|event| event.created_at)
+        .max())
+}
+
+fn get_push_events(
+    client: &Gitlab,
+    project_id: u64,
+    staff: &[String],
+) -> Result<Vec<Event>, Error> {
+    let events_endpoint = ProjectEvents {
+        project: project_id,
+        action: "pushed",
+    };
+    let events: Vec<Event> = paged(events_endpoint, Pagination::All)
+        .query(client)
+        .map_err(|e| Error::gitlab(e, "push events"))?;
+
+    Ok(events
+        .into_iter()
+        .filter(|event| !is_staff_push(staff, event))
+        .collect())
+}
+
+// Rows stay at their line's index, since g<N> comes from the line number: a blank line is one
+// empty field, and a # comment has no fields at all. Quoted fields, Windows line endings and the
+// byte order mark Excel puts at the start are all fine.
+fn parse_csv_file(filename: &String) -> Result<Vec<Vec<String>>, Error> {
+    let contents = fs::read_to_string(filename)
+        .map_err(|e| Error::io(format!("Failed to read {filename}"), e))?;
+    Ok(contents
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(parse_csv_line)
+        .collect())
+}
+
+// Each line is read on its own to keep the line numbers right, so a quoted field can't run over
+// several lines.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    if line.trim_start().starts_with('#') {
+        return Vec::new();
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(line.as_bytes());
+    let mut record = csv::StringRecord::new();
+    // The contents are already known to be UTF-8, which is all the reader could object to.
+    if !reader.read_record(&mut record).unwrap() {
+        return vec![String::new()];
+    }
+    record.iter().map(String::from).collect()
+}
+
+fn parse_extensions_file(
+    filename: &String,
+    timezone: Tz,
+) -> Result<HashMap<String, Extension>, Error> {
+    let mut result = HashMap::new();
+    for (i, line) in parse_csv_file(filename)?.into_iter().enumerate() {
+        match line.as_slice() {
+            [] => continue,
+            [who] if who.is_empty() => continue,
+            [who, extension] if !who.is_empty() => {
+                let extension = parse_extension(extension, timezone)
+                    .map_err(|e| Error::BadArgs(format!("Line {} of {filename}: {e}", i + 1)))?;
+                result.insert(who.clone(), extension);
+            }
+            _ => {
+                return Err(Error::BadArgs(format!(
+                    "Line {} of {filename} should be <username or g<N>>,<due date or +duration>",
+                    i + 1
+                )))
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Either a replacement due date, written like the regular one, or extra time such as +2d, +36h
+// or +90m.
+fn parse_extension(extension: &str, timezone: Tz) -> Result<Extension, String> {
+    if let Some(extra) = extension.strip_prefix('+') {
+        let invalid = || format!("Invalid extension duration {extension}");
+        let (unit_index, unit) = extra.char_indices().last().ok_or_else(invalid)?;
+        let amount: u64 = extra[..unit_index].parse().map_err(|_| invalid())?;
+        let minutes_per_unit = match unit {
+            'd' => MINS_PER_DAY as u64,
+            'h' => 60,
+            'm' => 1,
+            _ => return Err(format!("{}; use d, h or m", invalid())),
+        };
+        let seconds = amount
+            .checked_mul(minutes_per_unit)
+            .and_then(|minutes| minutes.checked_mul(60))
+            .ok_or_else(invalid)?;
+        return Ok(Extension::Extra(Duration::from_secs(seconds)));
     }
+
+    extension
+        .parse::<DueDate>()
+        .and_then(|due| due.in_timezone(timezone))
+        .map(Extension::DueDate)
+        .map_err(|e| format!("Invalid extension due date: {e}"))
 }
 
-fn read_token_file(filename: &String) -> String {
+fn read_token_file(filename: &String) -> Result<String, Error> {
     let mut token = fs::read_to_string(filename)
-        .unwrap_or_else(|_| panic!("Unable to read token from file {filename}"));
+        .map_err(|e| Error::io(format!("Unable to read token from file {filename}"), e))?;
     token.retain(|c| !c.is_whitespace());
-    token
+    Ok(token)
 }
 
 #[cfg(test)]
+// The original CSV tests build their expected rows one push at a time.
+#[allow(clippy::vec_init_then_push)]
 mod tests {
+    use std::collections::HashMap;
     use std::fs;
     use std::fs::{remove_file, File};
     use std::io::Write;
     use std::path::Path;
     use std::time::Duration;
 
-    use chrono::NaiveDateTime;
+    use chrono::{NaiveDateTime, TimeZone};
+    use chrono_tz::America::Vancouver;
     use chrono_tz::Canada::Eastern;
     use gitlab::Gitlab;
 
     use httpmock::prelude::*;
 
+    use crate::cli::{parse_args, Command};
+    use crate::config_file::{load_config_file, resolve_run_settings, ConfigFile};
+    use crate::error::Error;
+    use crate::lateness::LatenessPolicy;
+    use crate::paths::PathFilter;
+    use crate::report::ReportFormat;
+    use crate::sections::parse_sections_file;
     use crate::{
-        build_config, calculate_effective_due_date, calculate_lateness, get_last_commit,
-        get_late_days, parse_csv_file, read_token_file, validate_args_len, GitLabConfig,
-        DATE_TIME_FORMAT,
+        build_config, calculate_effective_due_date, calculate_lateness, calculate_project_due_date,
+        connect, csv_field, get_last_commit, get_late_days, is_staff_commit, is_staff_push,
+        parse_csv_file, parse_extension, parse_extensions_file, read_token_file, resolve_host, run,
+        Commit, Event, Extension, GitLabConfig, LastCommit, OutputFile, Repo, Submission,
+        TimestampSource, DATE_TIME_FORMAT,
     };
 
+    pub(crate) fn test_config(
+        group_name: &str,
+        starter_commit_hash: &str,
+        timestamp_source: TimestampSource,
+    ) -> GitLabConfig {
+        let due_date = NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
+        GitLabConfig {
+            designation: "a1".to_string(),
+            starter_commits: vec![starter_commit_hash.to_string()],
+            starter_ancestry: false,
+            group_name: group_name.to_string(),
+            timezone: Eastern,
+            due_date_time: due_date.and_local_timezone(Eastern).unwrap(),
+            tolerance: Duration::from_secs(900),
+            timestamp_source,
+            extensions: HashMap::new(),
+            sections: HashMap::new(),
+            section_due_dates: HashMap::new(),
+            branch: None,
+            tag: None,
+            all_branches: false,
+            jobs: 1,
+            report: None,
+            ledger: None,
+            lateness: LatenessPolicy::Days,
+            max_lateness: None,
+            staff: Vec::new(),
+            graded_paths: PathFilter::default(),
+        }
+    }
+
+    fn submitted(last_commit: LastCommit) -> Submission {
+        match last_commit {
+            LastCommit::Submitted(submission) => submission,
+            other => panic!("Expected a submission, got {other:?}"),
+        }
+    }
+
     #[test]
     fn late_days_zero_if_sub_day_before_due_date() {
         let due_date = NaiveDateTime::parse_from_str("2023-01-24 22:05", DATE_TIME_FORMAT).unwrap();
@@ -236,7 +1403,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-23 11:29", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 0);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            0.0
+        );
     }
 
     #[test]
@@ -248,7 +1418,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-24 11:29", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 0);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            0.0
+        );
     }
 
     #[test]
@@ -260,7 +1433,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-24 22:05", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 0);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            0.0
+        );
     }
 
     #[test]
@@ -272,7 +1448,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-25 08:12", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 1);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            1.0
+        );
     }
 
     #[test]
@@ -284,7 +1463,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-24 23:05", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 1);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            1.0
+        );
     }
 
     #[test]
@@ -296,7 +1478,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-24 22:10", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 1);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            1.0
+        );
     }
 
     #[test]
@@ -308,7 +1493,10 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-01-26 23:50", DATE_TIME_FORMAT).unwrap();
         let submit_date = submit_date.and_local_timezone(Eastern).unwrap();
 
-        assert_eq!(calculate_lateness(submit_date, due_date), 3);
+        assert_eq!(
+            calculate_lateness(submit_date, due_date, LatenessPolicy::Days),
+            3.0
+        );
     }
 
     #[test]
@@ -320,23 +1508,152 @@ mod tests {
             token_file.write_all(token.as_bytes()).unwrap();
         } // Let it go out of scope so it's closed
         let filename = String::from(file_name);
-        let read_token = read_token_file(&filename);
+        let read_token = read_token_file(&filename).unwrap();
         remove_file(Path::new(file_name)).unwrap();
         assert_eq!(read_token, token);
     }
 
     #[test]
-    fn token_is_trimmed_nicely() {
-        let token = "1234567890ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
-        let file_name = "tmp_token2.git";
-        {
-            let mut token_file = File::create(Path::new(file_name)).unwrap();
-            token_file.write_all("  ".as_bytes()).unwrap();
-            token_file.write_all(token.as_bytes()).unwrap();
-            token_file.write_all("  \n".as_bytes()).unwrap();
-        } // Let it go out of scope so it's closed
-        let filename = String::from(file_name);
-        let read_token = read_token_file(&filename);
+    fn missing_token_file_is_an_io_error() {
+        let error = read_token_file(&String::from("test/resources/missing.git")).unwrap_err();
+
+        assert!(matches!(error, Error::Io { .. }));
+        assert!(error
+            .to_string()
+            .starts_with("Unable to read token from file test/resources/missing.git"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn failed_output_write_is_an_io_error() {
+        let mut output_file = OutputFile::create(String::from("/dev/full")).unwrap();
+
+        let error = output_file.write_line("username,1\n").unwrap_err();
+
+        assert_eq!(7, error.exit_code());
+        assert!(error.to_string().starts_with("Unable to write /dev/full"));
+    }
+
+    #[test]
+    fn rejected_token_is_an_auth_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(401)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "401 Unauthorized"}"#);
+        });
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+
+        let Err(error) = connect(String::from(server_url), true, String::from("00")) else {
+            panic!("Expected an error");
+        };
+
+        assert_eq!(4, error.exit_code());
+        assert!(error.to_string().starts_with("Unable to log in to"));
+    }
+
+    #[test]
+    fn dry_run_problems_decide_the_exit_code() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(fs::read_to_string("test/resources/exampleuser.json").unwrap());
+        });
+        server.mock(|when, then| {
+            when.method(GET);
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let file_name = "tmp_token3.git";
+        fs::write(file_name, "00").unwrap();
+        let args = vec![
+            "cmd".to_string(),
+            "--host".to_string(),
+            server_url.to_string(),
+            "--insecure".to_string(),
+            "run".to_string(),
+            "--designation".to_string(),
+            "a8".to_string(),
+            "--starter-commit".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "--group".to_string(),
+            "ece459".to_string(),
+            "--due".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "--roster".to_string(),
+            "test/resources/simple.csv".to_string(),
+            "--token-file".to_string(),
+            file_name.to_string(),
+            "--dry-run".to_string(),
+        ];
+
+        let result = run(parse_args(args));
+        remove_file(Path::new(file_name)).unwrap();
+
+        let Err(error) = result else {
+            panic!("Expected an error");
+        };
+        assert_eq!(5, error.exit_code());
+        assert_eq!(
+            "1 problem(s) found; nothing was written.",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn tolerance_too_long_is_an_error_not_a_panic() {
+        for tolerance in ["200000000000000", "18446744073709551615"] {
+            let args = vec![
+                "cmd".to_string(),
+                "run".to_string(),
+                "--designation".to_string(),
+                "a1".to_string(),
+                "--starter-commit".to_string(),
+                "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+                "--group".to_string(),
+                "ece459-1231".to_string(),
+                "--due".to_string(),
+                "2023-01-27 14:30".to_string(),
+                "--tolerance".to_string(),
+                tolerance.to_string(),
+                "--roster".to_string(),
+                "csvfile.csv".to_string(),
+                "--token-file".to_string(),
+                "tokenfile.csv".to_string(),
+            ];
+
+            let Command::Run(run_args) = parse_args(args).command else {
+                panic!("Expected the run command");
+            };
+            let Err(error) =
+                build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap())
+            else {
+                panic!("Expected an error");
+            };
+
+            assert!(matches!(error, Error::BadArgs(_)));
+        }
+    }
+
+    #[test]
+    fn token_is_trimmed_nicely() {
+        let token = "1234567890ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        let file_name = "tmp_token2.git";
+        {
+            let mut token_file = File::create(Path::new(file_name)).unwrap();
+            token_file.write_all("  ".as_bytes()).unwrap();
+            token_file.write_all(token.as_bytes()).unwrap();
+            token_file.write_all("  \n".as_bytes()).unwrap();
+        } // Let it go out of scope so it's closed
+        let filename = String::from(file_name);
+        let read_token = read_token_file(&filename).unwrap();
         remove_file(Path::new(file_name)).unwrap();
         assert_eq!(read_token, token);
     }
@@ -349,7 +1666,7 @@ mod tests {
         inner.push(String::from("username"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -364,7 +1681,7 @@ mod tests {
         inner.push(String::from("u3sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -379,7 +1696,7 @@ mod tests {
         inner.push(String::from("u3sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -398,7 +1715,7 @@ mod tests {
         inner.push(String::from("u3sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -417,7 +1734,7 @@ mod tests {
         inner.push(String::from("u3sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -437,7 +1754,7 @@ mod tests {
         inner.push(String::from("u5sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
@@ -457,81 +1774,1627 @@ mod tests {
         inner.push(String::from("u4sernam"));
         expected.push(inner);
 
-        let parsed = parse_csv_file(&test_filename);
+        let parsed = parse_csv_file(&test_filename).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn can_parse_excel_csv() {
+        let test_filename = String::from("test/resources/excel.csv");
+        let expected: Vec<Vec<String>> = vec![
+            vec![],
+            vec![String::from("username"), String::from("u2sernam")],
+            vec![String::new()],
+            vec![String::from("u3sernam"), String::from("u4sernam")],
+            vec![String::from("u5sernam")],
+        ];
+
+        let parsed = parse_csv_file(&test_filename).unwrap();
 
         assert_eq!(parsed, expected);
     }
 
     #[test]
-    fn validate_args_expects_8() {
-        let args1 = vec![String::new(); 8];
-        let args2 = vec![String::new(); 7];
-        let args3 = vec![String::new(); 9];
-        let args4 = vec![String::new(); 1];
+    fn correctly_build_config() {
+        let args = vec![
+            "cmd".to_string(),
+            "a1".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "ece459-1231".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "15".to_string(),
+            "csvfile.csv".to_string(),
+            "tokenfile.csv".to_string(),
+        ];
+        let expected_date_time =
+            NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
+        let expected_date_time = expected_date_time.and_local_timezone(Eastern).unwrap();
+        let expected_tolerance = Duration::from_secs(900);
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+        let config =
+            build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap())
+                .unwrap();
+
+        assert_eq!("a1", config.designation);
+        assert_eq!("ece459-1231", config.group_name);
+        assert_eq!(expected_date_time, config.due_date_time);
+        assert_eq!(expected_tolerance, config.tolerance);
+        assert_eq!(
+            vec!["e308eadf8d161c28edbf1076684eb4f7"],
+            config.starter_commits
+        );
+        assert_eq!(TimestampSource::Commit, config.timestamp_source);
+        assert!(config.extensions.is_empty());
+    }
+
+    #[test]
+    fn build_config_from_named_args() {
+        let args = vec![
+            "cmd".to_string(),
+            "run".to_string(),
+            "--designation".to_string(),
+            "a1".to_string(),
+            "--starter-commit".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "--group".to_string(),
+            "ece459-1231".to_string(),
+            "--due".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "--roster".to_string(),
+            "csvfile.csv".to_string(),
+            "--token-file".to_string(),
+            "tokenfile.csv".to_string(),
+            "--timestamp-source".to_string(),
+            "push".to_string(),
+            "--extensions".to_string(),
+            "test/resources/extensions.csv".to_string(),
+        ];
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+        let config =
+            build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap())
+                .unwrap();
+
+        assert_eq!(Duration::from_secs(0), config.tolerance);
+        assert_eq!(TimestampSource::Push, config.timestamp_source);
+        assert_eq!(3, config.extensions.len());
+    }
+
+    #[test]
+    fn section_due_dates_must_match_a_section() {
+        let args = vec![
+            "cmd".to_string(),
+            "run".to_string(),
+            "--designation".to_string(),
+            "a1".to_string(),
+            "--starter-commit".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "--group".to_string(),
+            "ece459-1231".to_string(),
+            "--due".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "--roster".to_string(),
+            "csvfile.csv".to_string(),
+            "--token-file".to_string(),
+            "tokenfile.csv".to_string(),
+            "--sections".to_string(),
+            "test/resources/sections.csv".to_string(),
+            "--section-due".to_string(),
+            "001=2023-01-26 14:30,003=2023-01-28 14:30".to_string(),
+        ];
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+        let Err(error) =
+            build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap())
+        else {
+            panic!("Expected an error");
+        };
+
+        assert!(matches!(error, Error::BadArgs(_)));
+        assert_eq!(
+            "Section 003 has a due date, but nobody is in it",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn extensions_past_the_end_of_time_are_rejected() {
+        let args = vec![
+            "cmd".to_string(),
+            "run".to_string(),
+            "--designation".to_string(),
+            "a1".to_string(),
+            "--starter-commit".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "--group".to_string(),
+            "ece459-1231".to_string(),
+            "--due".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "--roster".to_string(),
+            "csvfile.csv".to_string(),
+            "--token-file".to_string(),
+            "tokenfile.csv".to_string(),
+            "--extensions".to_string(),
+            "test/resources/huge_extensions.csv".to_string(),
+        ];
+
+        let Command::Run(run_args) = parse_args(args).command else {
+            panic!("Expected the run command");
+        };
+        let Err(error) =
+            build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap())
+        else {
+            panic!("Expected an error");
+        };
+
+        assert!(matches!(error, Error::BadArgs(_)));
+        assert!(error.to_string().ends_with("is too far in the future"));
+    }
+
+    #[test]
+    fn max_lateness_must_be_a_number_at_least_zero() {
+        for (max_lateness, allowed) in [
+            ("0", true),
+            ("2.5", true),
+            ("-1", false),
+            ("NaN", false),
+            ("inf", false),
+        ] {
+            let args = vec![
+                "cmd".to_string(),
+                "run".to_string(),
+                "--designation".to_string(),
+                "a1".to_string(),
+                "--starter-commit".to_string(),
+                "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+                "--group".to_string(),
+                "ece459-1231".to_string(),
+                "--due".to_string(),
+                "2023-01-27 14:30".to_string(),
+                "--roster".to_string(),
+                "csvfile.csv".to_string(),
+                "--token-file".to_string(),
+                "tokenfile.csv".to_string(),
+                format!("--max-lateness={max_lateness}"),
+            ];
+
+            let Command::Run(run_args) = parse_args(args).command else {
+                panic!("Expected the run command");
+            };
+            let result =
+                build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap());
+
+            assert_eq!(allowed, result.is_ok(), "{max_lateness}");
+            if let Err(error) = result {
+                assert!(matches!(error, Error::BadArgs(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn ledger_needs_a_day_based_policy() {
+        for (policy, allowed) in [
+            ("days", true),
+            ("days/2", true),
+            ("hours", false),
+            ("5%/hour", false),
+        ] {
+            let args = vec![
+                "cmd".to_string(),
+                "run".to_string(),
+                "--designation".to_string(),
+                "a1".to_string(),
+                "--starter-commit".to_string(),
+                "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+                "--group".to_string(),
+                "ece459-1231".to_string(),
+                "--due".to_string(),
+                "2023-01-27 14:30".to_string(),
+                "--roster".to_string(),
+                "csvfile.csv".to_string(),
+                "--token-file".to_string(),
+                "tokenfile.csv".to_string(),
+                "--ledger".to_string(),
+                "ledger.csv".to_string(),
+                "--lateness".to_string(),
+                policy.to_string(),
+            ];
+
+            let Command::Run(run_args) = parse_args(args).command else {
+                panic!("Expected the run command");
+            };
+            let result =
+                build_config(&resolve_run_settings(&run_args, &ConfigFile::default()).unwrap());
+
+            assert_eq!(allowed, result.is_ok(), "{policy}");
+            if let Err(error) = result {
+                assert!(matches!(error, Error::BadArgs(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn host_defaults_to_uw_gitlab() {
+        let args = vec![
+            "cmd".to_string(),
+            "a1".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "ece459-1231".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "15".to_string(),
+            "csvfile.csv".to_string(),
+            "tokenfile.csv".to_string(),
+        ];
+        let mut cli = parse_args(args);
+        cli.host = None;
+        cli.insecure = false;
+
+        let (host, insecure) = resolve_host(&cli, &ConfigFile::default());
+
+        assert_eq!("git.uwaterloo.ca", host);
+        assert!(!insecure);
+    }
+
+    #[test]
+    fn host_prefers_command_line_over_config_file() {
+        let args = vec![
+            "cmd".to_string(),
+            "a1".to_string(),
+            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
+            "ece459-1231".to_string(),
+            "2023-01-27 14:30".to_string(),
+            "15".to_string(),
+            "csvfile.csv".to_string(),
+            "tokenfile.csv".to_string(),
+        ];
+        let config_file =
+            load_config_file(Some(&String::from("test/resources/late-days.toml"))).unwrap();
+        let mut cli = parse_args(args);
+        cli.host = None;
+        cli.insecure = false;
+
+        let (host, _) = resolve_host(&cli, &config_file);
+        assert_eq!("gitlab.example.com", host);
+
+        cli.host = Some(String::from("localhost:8080"));
+        cli.insecure = true;
+        let (host, insecure) = resolve_host(&cli, &config_file);
+        assert_eq!("localhost:8080", host);
+        assert!(insecure);
+    }
+
+    #[test]
+    fn can_parse_extensions_file() {
+        let test_filename = String::from("test/resources/extensions.csv");
+        let new_due_date =
+            NaiveDateTime::parse_from_str("2023-02-03 23:59", DATE_TIME_FORMAT).unwrap();
+        let new_due_date = new_due_date.and_local_timezone(Eastern).unwrap();
+
+        let extensions = parse_extensions_file(&test_filename, Eastern).unwrap();
+
+        assert_eq!(3, extensions.len());
+        assert_eq!(
+            Some(&Extension::Extra(Duration::from_secs(2 * 24 * 60 * 60))),
+            extensions.get("username")
+        );
+        assert_eq!(
+            Some(&Extension::DueDate(new_due_date)),
+            extensions.get("g3")
+        );
+        assert_eq!(
+            Some(&Extension::Extra(Duration::from_secs(36 * 60 * 60))),
+            extensions.get("u2sernam")
+        );
+    }
+
+    #[test]
+    fn can_parse_excel_extensions_file() {
+        let test_filename = String::from("test/resources/excel_extensions.csv");
+
+        let extensions = parse_extensions_file(&test_filename, Eastern).unwrap();
+
+        assert_eq!(2, extensions.len());
+        assert_eq!(
+            Some(&Extension::Extra(Duration::from_secs(2 * 24 * 60 * 60))),
+            extensions.get("username")
+        );
+        assert!(extensions.contains_key("g3"));
+    }
+
+    #[test]
+    fn parse_extension_durations() {
+        assert_eq!(
+            Ok(Extension::Extra(Duration::from_secs(90 * 60))),
+            parse_extension("+90m", Eastern)
+        );
+        assert!(parse_extension("+", Eastern).is_err());
+        assert!(parse_extension("+2w", Eastern).is_err());
+        assert!(parse_extension("two days", Eastern).is_err());
+        assert_eq!(
+            Err(String::from(
+                "Invalid extension duration +5日; use d, h or m"
+            )),
+            parse_extension("+5日", Eastern)
+        );
+        assert_eq!(
+            Err(String::from(
+                "Invalid extension duration +99999999999999999d"
+            )),
+            parse_extension("+99999999999999999d", Eastern)
+        );
+    }
+
+    #[test]
+    fn extension_due_dates_are_in_the_course_timezone() {
+        let extension = parse_extension("2023-02-03 23:59", Vancouver).unwrap();
+        let offset = parse_extension("2023-02-03T23:59-08:00", Eastern).unwrap();
+
+        assert_eq!(extension, offset);
+        assert!(parse_extension("2023-03-12 02:30", Eastern).is_err());
+    }
+
+    #[test]
+    fn project_due_date_uses_most_generous_extension() {
+        let due_date_time =
+            NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
+        let due_date_time = due_date_time.and_local_timezone(Eastern).unwrap();
+        let extensions =
+            parse_extensions_file(&String::from("test/resources/extensions.csv"), Eastern).unwrap();
+        let config = GitLabConfig {
+            designation: "p".to_string(),
+            starter_commits: Vec::new(),
+            due_date_time,
+            extensions,
+            ..test_config("ece459", "", TimestampSource::Commit)
+        };
+        let members = vec![String::from("username"), String::from("u2sernam")];
+
+        let (due_date, extension_used, _) =
+            calculate_project_due_date(&config, &String::from("g1"), &members).unwrap();
+        assert_eq!(
+            "2023-01-29 14:45",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(Some(String::from("username")), extension_used);
+
+        let (due_date, extension_used, _) =
+            calculate_project_due_date(&config, &String::from("g3"), &members).unwrap();
+        assert_eq!(
+            "2023-02-04 00:14",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(Some(String::from("g3")), extension_used);
+
+        let (due_date, extension_used, _) =
+            calculate_project_due_date(&config, &String::from("u4sernam"), &[]).unwrap();
+        assert_eq!(
+            "2023-01-27 14:45",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(None, extension_used);
+    }
+
+    #[test]
+    fn project_due_date_depends_on_the_section() {
+        let due_date_time = Eastern.with_ymd_and_hms(2023, 1, 27, 14, 30, 0).unwrap();
+        let config = GitLabConfig {
+            designation: "p".to_string(),
+            starter_commits: Vec::new(),
+            due_date_time,
+            extensions: parse_extensions_file(
+                &String::from("test/resources/extensions.csv"),
+                Eastern,
+            )
+            .unwrap(),
+            sections: parse_sections_file(&String::from("test/resources/sections.csv")).unwrap(),
+            section_due_dates: HashMap::from([
+                (
+                    String::from("001"),
+                    due_date_time - chrono::Duration::days(1),
+                ),
+                (
+                    String::from("002"),
+                    due_date_time + chrono::Duration::days(1),
+                ),
+            ]),
+            ..test_config("ece459", "", TimestampSource::Commit)
+        };
+
+        let (due_date, extension_used, section) =
+            calculate_project_due_date(&config, &String::from("u2sernam"), &[]).unwrap();
+        assert_eq!(
+            "2023-01-28 02:45",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(Some(String::from("u2sernam")), extension_used);
+        assert_eq!(Some(String::from("001")), section);
+
+        let members = vec![String::from("u3sernam"), String::from("u4sernam")];
+        let (due_date, extension_used, section) =
+            calculate_project_due_date(&config, &String::from("g2"), &members).unwrap();
+        assert_eq!(
+            "2023-01-28 14:45",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(None, extension_used);
+        assert_eq!(Some(String::from("002")), section);
+
+        let (due_date, _, section) =
+            calculate_project_due_date(&config, &String::from("u5sernam"), &[]).unwrap();
+        assert_eq!(
+            "2023-01-27 14:45",
+            due_date.format(DATE_TIME_FORMAT).to_string()
+        );
+        assert_eq!(None, section);
+    }
+
+    #[test]
+    fn test_calculate_effective_due_date() {
+        let due_date_time =
+            NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
+        let due_date_time = due_date_time.and_local_timezone(Eastern).unwrap();
+        let tolerance = Duration::from_secs(900);
+
+        let expected_due_date_time =
+            NaiveDateTime::parse_from_str("2023-01-27 14:45", DATE_TIME_FORMAT).unwrap();
+        let expected_due_date_time = expected_due_date_time.and_local_timezone(Eastern).unwrap();
+
+        let effective_due_date = calculate_effective_due_date(due_date_time, tolerance).unwrap();
+
+        assert_eq!(expected_due_date_time, effective_due_date);
+        let formatted_date = effective_due_date.format("%Y-%m-%d %H:%M %Z").to_string();
+        assert!(
+            "2023-01-27 14:45 EST".to_string().eq(&formatted_date)
+                || "2023-01-27 14:45 EDT".to_string().eq(&formatted_date)
+        )
+    }
+
+    #[test]
+    fn effective_due_date_out_of_range_is_an_error() {
+        let due_date_time = Eastern.with_ymd_and_hms(2023, 1, 27, 14, 30, 0).unwrap();
+
+        for extra in [100_000_000 * 24 * 60 * 60, u64::MAX] {
+            let result = calculate_effective_due_date(due_date_time, Duration::from_secs(extra));
+            assert!(matches!(result, Err(Error::BadArgs(_))));
+        }
+    }
+
+    #[test]
+    fn test_get_last_commit() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let last_commit = submitted(
+            get_last_commit(
+                &gitlab,
+                &test_config(&group, &starter_commit_hash, TimestampSource::Commit),
+                &proj,
+            )
+            .unwrap(),
+        );
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        assert_eq!(
+            "2023-01-27 03:44 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(
+            "7b5c3cc8be40ee161ae89a06bba6229da1032a0c",
+            last_commit.commit_id
+        );
+        assert_eq!(TimestampSource::Commit, last_commit.source);
+    }
+
+    #[test]
+    fn test_get_last_commit_uses_push_time() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+        let events_json = fs::read_to_string("test/resources/exampleevents.json")
+            .unwrap_or_else(|_| panic!("Unable to read events data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        let get_events_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/events")
+                .query_param("action", "pushed");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(events_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let last_commit = submitted(
+            get_last_commit(
+                &gitlab,
+                &test_config(&group, &starter_commit_hash, TimestampSource::Push),
+                &proj,
+            )
+            .unwrap(),
+        );
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        get_events_mock.assert();
+        assert_eq!(
+            "2023-01-27 15:12 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(TimestampSource::Push, last_commit.source);
+    }
+
+    #[test]
+    fn test_get_last_commit_falls_back_without_push_event() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        let get_events_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/projects/4/events");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("[]");
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let last_commit = submitted(
+            get_last_commit(
+                &gitlab,
+                &test_config(&group, &starter_commit_hash, TimestampSource::Push),
+                &proj,
+            )
+            .unwrap(),
+        );
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        get_events_mock.assert();
+        assert_eq!(
+            "2023-01-27 03:44 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(TimestampSource::Commit, last_commit.source);
+    }
+
+    #[test]
+    fn test_last_commit_is_null_when_same_as_starter_code() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("7b5c3cc8be40ee161ae89a06bba6229da1032a0c");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let last_commit = get_last_commit(
+            &gitlab,
+            &test_config(&group, &starter_commit_hash, TimestampSource::Commit),
+            &proj,
+        )
+        .unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        assert!(matches!(last_commit, LastCommit::NoChange))
+    }
+
+    #[test]
+    fn test_get_last_commit_follows_the_project_default_branch() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"))
+            .replace(
+                r#""default_branch": "main""#,
+                r#""default_branch": "master""#,
+            );
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/master");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let last_commit = submitted(
+            get_last_commit(
+                &gitlab,
+                &test_config(&group, &starter_commit_hash, TimestampSource::Commit),
+                &proj,
+            )
+            .unwrap(),
+        );
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        assert_eq!("master", last_commit.branch);
+    }
+
+    #[test]
+    fn test_get_last_commit_uses_configured_branch() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/submission");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(&group, &starter_commit_hash, TimestampSource::Commit);
+        config.branch = Some(String::from("submission"));
+        let last_commit = submitted(get_last_commit(&gitlab, &config, &proj).unwrap());
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        assert_eq!("submission", last_commit.branch);
+    }
+
+    #[test]
+    fn test_get_last_commit_looks_at_all_branches() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branches_json = fs::read_to_string("test/resources/examplebranches.json")
+            .unwrap_or_else(|_| panic!("Unable to read branches data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branches_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branches_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Commit,
+        );
+        config.all_branches = true;
+        let last_commit = submitted(get_last_commit(&gitlab, &config, &proj).unwrap());
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branches_mock.assert();
+        assert_eq!("feature", last_commit.branch);
+        assert_eq!(
+            "7b5c3cc8be40ee161ae89a06bba6229da1032a0c",
+            last_commit.commit_id
+        );
+        assert_eq!(
+            Some(String::from(
+                "submission is on feature, not the default branch main"
+            )),
+            last_commit.warning
+        );
+    }
+
+    #[test]
+    fn test_get_last_commit_compares_branches_by_push_time() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branches_json = fs::read_to_string("test/resources/examplebranches.json")
+            .unwrap_or_else(|_| panic!("Unable to read branches data"));
+        let events_json = fs::read_to_string("test/resources/examplebranchevents.json")
+            .unwrap_or_else(|_| panic!("Unable to read events data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branches_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branches_json);
+        });
+        let get_events_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/events")
+                .query_param("action", "pushed");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(events_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Push,
+        );
+        config.all_branches = true;
+        let last_commit = submitted(get_last_commit(&gitlab, &config, &proj).unwrap());
+
+        // feature has the later committed date, but experiment was pushed later.
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branches_mock.assert();
+        get_events_mock.assert_hits(2);
+        assert_eq!("experiment", last_commit.branch);
+        assert_eq!(
+            "5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f",
+            last_commit.commit_id
+        );
+        assert_eq!(
+            "2023-01-27 16:05 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(TimestampSource::Push, last_commit.source);
+    }
+
+    fn ancestry_test(compare_json: String) -> LastCommit {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        let get_compare_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/compare")
+                .query_param("from", "4ad91d3c1144c406e50c7b33bae684bd6837faf8")
+                .query_param("to", "main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(compare_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Commit,
+        );
+        config
+            .starter_commits
+            .push(String::from("9f3b2a1c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f80"));
+        config.starter_ancestry = true;
+        let last_commit = get_last_commit(&gitlab, &config, &proj).unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        get_compare_mock.assert();
+        last_commit
+    }
+
+    #[test]
+    fn test_get_last_commit_skips_starter_updates() {
+        let compare_json = fs::read_to_string("test/resources/examplecompare.json")
+            .unwrap_or_else(|_| panic!("Unable to read compare data"));
+
+        let last_commit = submitted(ancestry_test(compare_json));
+
+        assert_eq!(
+            "c0ffee0123456789abcdef0123456789abcdef01",
+            last_commit.commit_id
+        );
+        assert_eq!(
+            "2023-01-27 22:10 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_get_last_commit_is_null_when_only_starter_was_updated() {
+        let compare_json = fs::read_to_string("test/resources/examplecompare.json")
+            .unwrap_or_else(|_| panic!("Unable to read compare data"));
+        let mut comparison: serde_json::Value = serde_json::from_str(&compare_json).unwrap();
+        comparison["commits"].as_array_mut().unwrap().pop();
+
+        let last_commit = ancestry_test(comparison.to_string());
+
+        assert!(matches!(last_commit, LastCommit::NoChange));
+    }
+
+    #[test]
+    fn test_get_last_commit_is_null_when_behind_starter() {
+        let last_commit = ancestry_test(String::from(r#"{"commit": null, "commits": []}"#));
+
+        assert!(matches!(last_commit, LastCommit::NoChange));
+    }
+
+    fn staff_commit_test(staff: &[&str], starter_commit_hash: &str) -> LastCommit {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranchbystaff.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+        let commits_json = fs::read_to_string("test/resources/examplecommits.json")
+            .unwrap_or_else(|_| panic!("Unable to read commits data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        let get_commits_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/commits")
+                .query_param("ref_name", "main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(commits_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config("ece459", starter_commit_hash, TimestampSource::Commit);
+        config.staff = staff.iter().map(|who| who.to_string()).collect();
+        let last_commit = get_last_commit(&gitlab, &config, &proj).unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        get_commits_mock.assert();
+        last_commit
+    }
+
+    #[test]
+    fn test_get_last_commit_skips_staff_commits() {
+        let last_commit = submitted(staff_commit_test(
+            &["ta1", "ci-bot@example.com"],
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+        ));
+
+        assert_eq!(
+            "7b5c3cc8be40ee161ae89a06bba6229da1032a0c",
+            last_commit.commit_id
+        );
+        assert_eq!(
+            "2023-01-27 03:44 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_get_last_commit_is_null_when_only_staff_changed_it() {
+        let last_commit = staff_commit_test(
+            &["ta1", "ci-bot@example.com", "john"],
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+        );
+
+        assert!(matches!(last_commit, LastCommit::NoChange));
+    }
+
+    #[test]
+    fn test_get_last_commit_skips_ungraded_changes() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranchbystaff.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+        let commits_json = fs::read_to_string("test/resources/examplecommits.json")
+            .unwrap_or_else(|_| panic!("Unable to read commits data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        let get_commits_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/commits")
+                .query_param("ref_name", "main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(commits_json);
+        });
+        let diff_mocks: Vec<_> = [
+            ("e2c1a5b0f3d94e7a8c6b1d2e3f4a5b6c7d8e9f00", "README.md"),
+            (
+                "d41f7e2a9b8c3d4e5f6a7b8c9d0e1f2a3b4c5d6e",
+                "results/out.txt",
+            ),
+            ("7b5c3cc8be40ee161ae89a06bba6229da1032a0c", "src/main.rs"),
+        ]
+        .into_iter()
+        .map(|(commit, path)| {
+            server.mock(|when, then| {
+                when.method(GET).path(format!(
+                    "/api/v4/projects/4/repository/commits/{commit}/diff"
+                ));
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(format!(
+                        r#"[{{"old_path": "{path}", "new_path": "{path}"}}]"#
+                    ));
+            })
+        })
+        .collect();
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Commit,
+        );
+        config.graded_paths = PathFilter::new(
+            &[],
+            &[String::from("README.md"), String::from("results/**")],
+        )
+        .unwrap();
+        let last_commit = submitted(get_last_commit(&gitlab, &config, &proj).unwrap());
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        get_commits_mock.assert();
+        for diff_mock in diff_mocks {
+            diff_mock.assert();
+        }
+        assert_eq!(
+            "7b5c3cc8be40ee161ae89a06bba6229da1032a0c",
+            last_commit.commit_id
+        );
+        assert_eq!(2, last_commit.ignored.len());
+        assert_eq!(
+            "e2c1a5b0f3d94e7a8c6b1d2e3f4a5b6c7d8e9f00",
+            last_commit.ignored[0].commit
+        );
+        assert_eq!("only changes ungraded paths", last_commit.ignored[1].reason);
+    }
+
+    #[test]
+    fn staff_match_by_username_name_or_email() {
+        let commit: Commit = serde_json::from_str(
+            r#"{"id": "e2c1a5b0f3d94e7a8c6b1d2e3f4a5b6c7d8e9f00", "author_name": "TA One",
+                "author_email": "TA1@uwaterloo.ca", "committed_date": "2023-01-28T10:02:11-05:00",
+                "parent_ids": []}"#,
+        )
+        .unwrap();
+
+        assert!(is_staff_commit(&[String::from("ta1")], &commit));
+        assert!(is_staff_commit(&[String::from("TA One")], &commit));
+        assert!(is_staff_commit(
+            &[String::from("ta1@uwaterloo.ca")],
+            &commit
+        ));
+        assert!(!is_staff_commit(&[String::from("ta2")], &commit));
+        assert!(!is_staff_commit(
+            &[String::from("ta1@example.com")],
+            &commit
+        ));
+        assert!(!is_staff_commit(&[], &commit));
+    }
+
+    #[test]
+    fn staff_pushes_match_by_username_only() {
+        let event: Event = serde_json::from_str(
+            r#"{"created_at": "2023-01-28T15:02:11.000Z", "author_username": "jzarnett",
+                "push_data": null}"#,
+        )
+        .unwrap();
+
+        assert!(is_staff_push(&[String::from("jzarnett")], &event));
+        assert!(is_staff_push(&[String::from("JZarnett")], &event));
+        assert!(!is_staff_push(
+            &[String::from("jzarnett@uwaterloo.ca")],
+            &event
+        ));
+        assert!(!is_staff_push(&[String::from("ta1")], &event));
+    }
+
+    fn tag_test(tag: &str, timestamp_source: TimestampSource) -> LastCommit {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let tags_json = fs::read_to_string("test/resources/exampletags.json")
+            .unwrap_or_else(|_| panic!("Unable to read tags data"));
+        let events_json = fs::read_to_string("test/resources/exampleevents.json")
+            .unwrap_or_else(|_| panic!("Unable to read events data"));
+
+        let proj = String::from("a1-username");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        let get_tags_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/projects/4/repository/tags");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(tags_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/events")
+                .query_param("action", "pushed");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(events_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            timestamp_source,
+        );
+        config.tag = Some(glob::Pattern::new(tag).unwrap());
+        let last_commit = get_last_commit(&gitlab, &config, &proj).unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_tags_mock.assert();
+        last_commit
+    }
+
+    #[test]
+    fn test_get_last_commit_uses_latest_matching_tag() {
+        let last_commit = submitted(tag_test("submit-*", TimestampSource::Commit));
+
+        assert_eq!("tags/submit-2", last_commit.branch);
+        assert_eq!(
+            "7b5c3cc8be40ee161ae89a06bba6229da1032a0c",
+            last_commit.commit_id
+        );
+        assert_eq!(
+            "2023-01-27 16:30 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(TimestampSource::Tag, last_commit.source);
+    }
+
+    #[test]
+    fn test_get_last_commit_uses_tag_push_time() {
+        let last_commit = submitted(tag_test("submit-2", TimestampSource::Push));
+
+        assert_eq!("tags/submit-2", last_commit.branch);
+        assert_eq!(
+            "2023-01-27 16:31 EST".to_string(),
+            last_commit
+                .timestamp
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        );
+        assert_eq!(TimestampSource::Push, last_commit.source);
+    }
+
+    #[test]
+    fn test_get_last_commit_is_no_change_when_tag_is_on_starter() {
+        let last_commit = tag_test("submit-1", TimestampSource::Commit);
+
+        assert!(matches!(last_commit, LastCommit::NoChange));
+    }
+
+    #[test]
+    fn test_get_last_commit_is_no_submission_without_tag() {
+        let last_commit = tag_test("submission", TimestampSource::Commit);
+
+        assert!(matches!(last_commit, LastCommit::NoSubmission));
+    }
+
+    #[test]
+    fn test_get_last_commit_reports_missing_branch() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+
+        let group = String::from("ece459");
+        let proj = String::from("a1-username");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Branch Not Found"}"#);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let reason = get_last_commit(
+            &gitlab,
+            &test_config(&group, &starter_commit_hash, TimestampSource::Commit),
+            &proj,
+        )
+        .unwrap_err();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        assert!(matches!(reason, Error::BranchNotFound { .. }));
+        assert!(
+            reason.to_string().starts_with("branch main not found"),
+            "{reason}"
+        );
+    }
+
+    #[test]
+    fn test_get_last_commit_tells_outages_from_missing_projects() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+
+        let group = String::from("ece459");
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_broken_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-username");
+            then.status(500)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "500 Internal Server Error"}"#);
+        });
+        let get_forbidden_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-u2sernam");
+            then.status(403)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "403 Forbidden"}"#);
+        });
+        let get_missing_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fa1-u3sernam");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
 
-        let validate1 = validate_args_len(&args1);
-        let validate2 = validate_args_len(&args2);
-        let validate3 = validate_args_len(&args3);
-        let validate4 = validate_args_len(&args4);
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let config = test_config(&group, &starter_commit_hash, TimestampSource::Commit);
+        let exit_code = |proj: &str| {
+            get_last_commit(&gitlab, &config, &String::from(proj))
+                .unwrap_err()
+                .exit_code()
+        };
 
-        assert_eq!(validate1, true);
-        assert_eq!(validate2, false);
-        assert_eq!(validate3, false);
-        assert_eq!(validate4, false);
+        assert_eq!(1, exit_code("a1-username"));
+        assert_eq!(4, exit_code("a1-u2sernam"));
+        assert_eq!(5, exit_code("a1-u3sernam"));
+        get_broken_mock.assert();
+        get_forbidden_mock.assert();
+        get_missing_mock.assert();
     }
 
     #[test]
-    fn correctly_build_config() {
-        let args = vec![
-            "cmd".to_string(),
-            "a1".to_string(),
-            "e308eadf8d161c28edbf1076684eb4f7".to_string(),
-            "ece459-1231".to_string(),
-            "2023-01-27 14:30".to_string(),
-            "15".to_string(),
-            "csvfile.csv".to_string(),
-            "tokenfile.csv".to_string(),
-        ];
-        let expected_date_time =
-            NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
-        let expected_date_time = expected_date_time.and_local_timezone(Eastern).unwrap();
-        let expected_tolerance = Duration::from_secs(900);
+    fn test_get_late_days() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
-        let config = build_config(&args);
+        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
 
-        assert_eq!("a1", config.designation);
-        assert_eq!("ece459-1231", config.group_name);
-        assert_eq!(expected_date_time, config.due_date_time);
-        assert_eq!(expected_tolerance, config.tolerance);
-        assert_eq!(
-            "e308eadf8d161c28edbf1076684eb4f7",
-            config.starter_commit_hash
-        )
-    }
+        let config = GitLabConfig {
+            designation: "a1".to_string(),
+            ..test_config("ece459", &starter_commit_hash, TimestampSource::Commit)
+        };
+        let repos = vec![Repo::new(1, vec![String::from("username")])];
 
-    #[test]
-    fn test_calculate_effective_due_date() {
-        let due_date_time =
-            NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
-        let due_date_time = due_date_time.and_local_timezone(Eastern).unwrap();
-        let tolerance = Duration::from_secs(900);
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
 
-        let expected_due_date_time =
-            NaiveDateTime::parse_from_str("2023-01-27 14:45", DATE_TIME_FORMAT).unwrap();
-        let expected_due_date_time = expected_due_date_time.and_local_timezone(Eastern).unwrap();
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
 
-        let effective_due_date = calculate_effective_due_date(due_date_time, tolerance);
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        get_late_days(gitlab, repos, config).unwrap();
 
-        assert_eq!(expected_due_date_time, effective_due_date);
-        let formatted_date = effective_due_date.format("%Y-%m-%d %H:%M %Z").to_string();
-        assert!(
-            "2023-01-27 14:45 EST".to_string().eq(&formatted_date)
-                || "2023-01-27 14:45 EDT".to_string().eq(&formatted_date)
-        )
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        get_proj_mock.assert();
+        get_branch_mock.assert();
+        let expected_output_file = "ece459-a1-latedays.csv";
+        let expected_nochanges_file = "ece459-a1-nochange.csv";
+        let expected_timestamps_file = "ece459-a1-timestamps.csv";
+        let expected_errors_file = "ece459-a1-errors.csv";
+        let expected_exceeded_file = "ece459-a1-exceeded.csv";
+        let expected_no_submission_file = "ece459-a1-nosubmission.csv";
+        let output_contents = fs::read_to_string(expected_output_file)
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        assert_eq!("username,0\n", output_contents);
+        let timestamps_contents = fs::read_to_string(expected_timestamps_file)
+            .unwrap_or_else(|_| panic!("Unable to read timestamps data"));
+        assert_eq!(
+            "ece459-a1-username,7b5c3cc8be40ee161ae89a06bba6229da1032a0c,2023-01-27T03:44:20-05:00,commit,2023-01-27T14:45:00-05:00,,main,\n",
+            timestamps_contents
+        );
+        let errors_contents = fs::read_to_string(expected_errors_file)
+            .unwrap_or_else(|_| panic!("Unable to read errors data"));
+        assert_eq!("", errors_contents);
+
+        remove_file(Path::new(expected_output_file)).unwrap();
+        remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
     }
 
     #[test]
-    fn test_get_last_commit() {
+    fn test_get_late_days_group() {
         let _ = env_logger::try_init();
         let user_json = fs::read_to_string("test/resources/exampleuser.json")
             .unwrap_or_else(|_| panic!("Unable to read user data"));
@@ -540,9 +3403,17 @@ mod tests {
         let branch_json = fs::read_to_string("test/resources/examplebranch.json")
             .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
-        let group = String::from("ece459");
-        let proj = String::from("a1-username");
         let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
+
+        let config = GitLabConfig {
+            designation: "a2".to_string(),
+            ..test_config("ece459", &starter_commit_hash, TimestampSource::Commit)
+        };
+        let repos = vec![Repo::new(
+            1,
+            vec![String::from("username"), String::from("u2sernam")],
+        )];
+
         let server = MockServer::start();
         let get_user_mock = server.mock(|when, then| {
             when.method(GET).path("/api/v4/user");
@@ -552,7 +3423,7 @@ mod tests {
         });
         let get_proj_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/api/v4/projects/ece459%2Fa1-username");
+                .path("/api/v4/projects/ece459%2Fece459-a2-g1");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(project_json);
@@ -560,7 +3431,7 @@ mod tests {
 
         let get_branch_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/api/v4/projects/4/repository/branches/main"));
+                .path("/api/v4/projects/4/repository/branches/main");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(branch_json);
@@ -569,20 +3440,32 @@ mod tests {
         let server_url = server.base_url();
         let server_url = server_url.strip_prefix("http://").unwrap();
         let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
-        let last_commit = get_last_commit(&gitlab, &group, &starter_commit_hash, &proj).unwrap();
+        get_late_days(gitlab, repos, config).unwrap();
 
         // Check that the URL was actually called!
         get_user_mock.assert();
         get_proj_mock.assert();
         get_branch_mock.assert();
-        assert_eq!(
-            "2023-01-27 03:44 EST".to_string(),
-            last_commit.format("%Y-%m-%d %H:%M %Z").to_string()
-        );
+        let expected_output_file = "ece459-a2-latedays.csv";
+        let expected_nochanges_file = "ece459-a2-nochange.csv";
+        let expected_timestamps_file = "ece459-a2-timestamps.csv";
+        let expected_errors_file = "ece459-a2-errors.csv";
+        let expected_exceeded_file = "ece459-a2-exceeded.csv";
+        let expected_no_submission_file = "ece459-a2-nosubmission.csv";
+        let output_contents = fs::read_to_string(expected_output_file)
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        assert_eq!("username,0\nu2sernam,0\n", output_contents);
+
+        remove_file(Path::new(expected_output_file)).unwrap();
+        remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
     }
 
     #[test]
-    fn test_last_commit_is_null_when_same_as_starter_code() {
+    fn test_get_late_days_keeps_going_after_a_failure() {
         let _ = env_logger::try_init();
         let user_json = fs::read_to_string("test/resources/exampleuser.json")
             .unwrap_or_else(|_| panic!("Unable to read user data"));
@@ -591,9 +3474,18 @@ mod tests {
         let branch_json = fs::read_to_string("test/resources/examplebranch.json")
             .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
-        let group = String::from("ece459");
-        let proj = String::from("a1-username");
-        let starter_commit_hash = String::from("7b5c3cc8be40ee161ae89a06bba6229da1032a0c");
+        let mut config = test_config(
+            "ece459",
+            "79ca81e76a65ff5009596c6e60b99ad0",
+            TimestampSource::Commit,
+        );
+        config.designation = "a6".to_string();
+        config.report = Some(ReportFormat::Jsonl);
+        let repos = vec![
+            Repo::new(1, vec![String::from("renamed")]),
+            Repo::new(2, vec![String::from("username")]),
+        ];
+
         let server = MockServer::start();
         let get_user_mock = server.mock(|when, then| {
             when.method(GET).path("/api/v4/user");
@@ -601,9 +3493,16 @@ mod tests {
                 .header("content-type", "application/json")
                 .body(user_json);
         });
+        let get_missing_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a6-renamed");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
         let get_proj_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/api/v4/projects/ece459%2Fa1-username");
+                .path("/api/v4/projects/ece459%2Fece459-a6-username");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(project_json);
@@ -611,7 +3510,7 @@ mod tests {
 
         let get_branch_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/api/v4/projects/4/repository/branches/main"));
+                .path("/api/v4/projects/4/repository/branches/main");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(branch_json);
@@ -620,17 +3519,59 @@ mod tests {
         let server_url = server.base_url();
         let server_url = server_url.strip_prefix("http://").unwrap();
         let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
-        let last_commit = get_last_commit(&gitlab, &group, &starter_commit_hash, &proj);
+        let failures = get_late_days(gitlab, repos, config).unwrap();
 
         // Check that the URL was actually called!
         get_user_mock.assert();
+        get_missing_proj_mock.assert();
         get_proj_mock.assert();
         get_branch_mock.assert();
-        assert_eq!(last_commit.is_none(), true)
+        assert_eq!(1, failures.len());
+        assert_eq!(5, failures[0].exit_code());
+        let expected_output_file = "ece459-a6-latedays.csv";
+        let expected_nochanges_file = "ece459-a6-nochange.csv";
+        let expected_timestamps_file = "ece459-a6-timestamps.csv";
+        let expected_errors_file = "ece459-a6-errors.csv";
+        let expected_exceeded_file = "ece459-a6-exceeded.csv";
+        let expected_no_submission_file = "ece459-a6-nosubmission.csv";
+        let output_contents = fs::read_to_string(expected_output_file)
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        assert_eq!("username,0\n", output_contents);
+        let errors_contents = fs::read_to_string(expected_errors_file)
+            .unwrap_or_else(|_| panic!("Unable to read errors data"));
+        assert!(
+            errors_contents.starts_with("ece459-a6-renamed,project not found"),
+            "{errors_contents}"
+        );
+        assert_eq!(1, errors_contents.lines().count());
+        let expected_report_file = "ece459-a6-report.jsonl";
+        let report_contents = fs::read_to_string(expected_report_file)
+            .unwrap_or_else(|_| panic!("Unable to read report data"));
+        let report: Vec<serde_json::Value> = report_contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!("ece459/ece459-a6-renamed", report[0]["project"]);
+        assert_eq!("error", report[0]["status"]);
+        assert_eq!("on-time", report[1]["status"]);
+        assert_eq!(4, report[1]["project_id"]);
+        assert_eq!("main", report[1]["branch"]);
+        assert_eq!("2023-01-27T08:44:20+00:00", report[1]["timestamp_utc"]);
+        assert_eq!("2023-01-27T03:44:20-05:00", report[1]["timestamp_local"]);
+        assert_eq!(0, report[1]["minutes_late"]);
+        assert_eq!(0.0, report[1]["late_days"]);
+
+        remove_file(Path::new(expected_output_file)).unwrap();
+        remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
+        remove_file(Path::new(expected_report_file)).unwrap();
     }
 
     #[test]
-    fn test_get_late_days() {
+    fn test_get_late_days_updates_ledger() {
         let _ = env_logger::try_init();
         let user_json = fs::read_to_string("test/resources/exampleuser.json")
             .unwrap_or_else(|_| panic!("Unable to read user data"));
@@ -639,22 +3580,102 @@ mod tests {
         let branch_json = fs::read_to_string("test/resources/examplebranch.json")
             .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
-        let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
-        let due_date = NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
-        let due_date = due_date.and_local_timezone(Eastern).unwrap();
-        let default_tolerance = Duration::from_secs(900);
+        let expected_ledger_file = "ece459-a9-ledger.csv";
+        fs::write(
+            expected_ledger_file,
+            "a1,username,2\na9,renamed,1\na9,username,3\n",
+        )
+        .unwrap();
+        let mut config = test_config(
+            "ece459",
+            "79ca81e76a65ff5009596c6e60b99ad0",
+            TimestampSource::Commit,
+        );
+        config.designation = "a9".to_string();
+        config.ledger = Some(expected_ledger_file.to_string());
+        let repos = vec![
+            Repo::new(1, vec![String::from("renamed")]),
+            Repo::new(2, vec![String::from("username")]),
+        ];
 
-        let config = GitLabConfig {
-            designation: "a1".to_string(),
-            starter_commit_hash,
-            group_name: "ece459".to_string(),
-            due_date_time: due_date,
-            tolerance: default_tolerance,
-        };
-        let mut repo_members = Vec::new();
-        let mut inner = Vec::new();
-        inner.push(String::from("username"));
-        repo_members.push(inner);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        let get_missing_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a9-renamed");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
+        let get_proj_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a9-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let failures = get_late_days(gitlab, repos, config).unwrap();
+
+        get_missing_proj_mock.assert();
+        get_proj_mock.assert();
+        assert_eq!(1, failures.len());
+        // The project that couldn't be checked keeps its earlier charge.
+        let ledger_contents = fs::read_to_string(expected_ledger_file)
+            .unwrap_or_else(|_| panic!("Unable to read ledger data"));
+        assert_eq!(
+            "a1,username,2\na9,renamed,1\na9,username,0\n",
+            ledger_contents
+        );
+
+        for file_name in [
+            "ece459-a9-latedays.csv",
+            "ece459-a9-nochange.csv",
+            "ece459-a9-timestamps.csv",
+            "ece459-a9-errors.csv",
+            "ece459-a9-exceeded.csv",
+            "ece459-a9-nosubmission.csv",
+            expected_ledger_file,
+        ] {
+            remove_file(Path::new(file_name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_get_late_days_past_the_maximum() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let mut config = test_config(
+            "ece459",
+            "79ca81e76a65ff5009596c6e60b99ad0",
+            TimestampSource::Commit,
+        );
+        config.designation = "a7".to_string();
+        let due_date = NaiveDateTime::parse_from_str("2023-01-20 14:30", DATE_TIME_FORMAT).unwrap();
+        config.due_date_time = due_date.and_local_timezone(Eastern).unwrap();
+        config.max_lateness = Some(3.0);
+        let repos = vec![Repo::new(1, vec![String::from("username")])];
 
         let server = MockServer::start();
         let get_user_mock = server.mock(|when, then| {
@@ -665,7 +3686,7 @@ mod tests {
         });
         let get_proj_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/api/v4/projects/ece459%2Fece459-a1-username");
+                .path("/api/v4/projects/ece459%2Fece459-a7-username");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(project_json);
@@ -673,7 +3694,7 @@ mod tests {
 
         let get_branch_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/api/v4/projects/4/repository/branches/main"));
+                .path("/api/v4/projects/4/repository/branches/main");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(branch_json);
@@ -682,24 +3703,126 @@ mod tests {
         let server_url = server.base_url();
         let server_url = server_url.strip_prefix("http://").unwrap();
         let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
-        get_late_days(gitlab, repo_members, config);
+        get_late_days(gitlab, repos, config).unwrap();
 
         // Check that the URL was actually called!
         get_user_mock.assert();
         get_proj_mock.assert();
         get_branch_mock.assert();
-        let expected_output_file = "ece459-a1-latedays.csv";
-        let expected_nochanges_file = "ece459-a1-nochange.csv";
+        let expected_output_file = "ece459-a7-latedays.csv";
+        let expected_nochanges_file = "ece459-a7-nochange.csv";
+        let expected_timestamps_file = "ece459-a7-timestamps.csv";
+        let expected_errors_file = "ece459-a7-errors.csv";
+        let expected_exceeded_file = "ece459-a7-exceeded.csv";
+        let expected_no_submission_file = "ece459-a7-nosubmission.csv";
         let output_contents = fs::read_to_string(expected_output_file)
             .unwrap_or_else(|_| panic!("Unable to read user data"));
-        assert_eq!("username,0\n", output_contents);
+        assert_eq!("", output_contents);
+        let exceeded_contents = fs::read_to_string(expected_exceeded_file)
+            .unwrap_or_else(|_| panic!("Unable to read exceeded data"));
+        assert_eq!("username\n", exceeded_contents);
 
         remove_file(Path::new(expected_output_file)).unwrap();
         remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
     }
 
     #[test]
-    fn test_get_late_days_group() {
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!("not found", csv_field("not found"));
+        assert_eq!("\"a, \"\"b\"\"\"", csv_field("a, \"b\""));
+    }
+
+    #[test]
+    fn test_get_late_days_in_parallel_keeps_roster_order() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+
+        let config = GitLabConfig {
+            designation: "a5".to_string(),
+            jobs: 3,
+            ..test_config(
+                "ece459",
+                "79ca81e76a65ff5009596c6e60b99ad0",
+                TimestampSource::Commit,
+            )
+        };
+        let students = ["username", "u2sernam", "u3sernam", "u4sernam", "u5sernam"];
+        let repos: Vec<Repo> = students
+            .iter()
+            .enumerate()
+            .map(|(i, student)| Repo::new(i + 1, vec![student.to_string()]))
+            .collect();
+
+        let server = MockServer::start();
+        let get_user_mock = server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(&user_json);
+        });
+        let get_proj_mocks: Vec<_> = students
+            .iter()
+            .map(|student| {
+                server.mock(|when, then| {
+                    when.method(GET)
+                        .path(format!("/api/v4/projects/ece459%2Fece459-a5-{student}"));
+                    then.status(200)
+                        .header("content-type", "application/json")
+                        .body(&project_json);
+                })
+            })
+            .collect();
+        let get_branch_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(&branch_json);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        get_late_days(gitlab, repos, config).unwrap();
+
+        // Check that the URL was actually called!
+        get_user_mock.assert();
+        for get_proj_mock in get_proj_mocks {
+            get_proj_mock.assert();
+        }
+        get_branch_mock.assert_hits(students.len());
+        let expected_output_file = "ece459-a5-latedays.csv";
+        let expected_nochanges_file = "ece459-a5-nochange.csv";
+        let expected_timestamps_file = "ece459-a5-timestamps.csv";
+        let expected_errors_file = "ece459-a5-errors.csv";
+        let expected_exceeded_file = "ece459-a5-exceeded.csv";
+        let expected_no_submission_file = "ece459-a5-nosubmission.csv";
+        let output_contents = fs::read_to_string(expected_output_file)
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        assert_eq!(
+            "username,0\nu2sernam,0\nu3sernam,0\nu4sernam,0\nu5sernam,0\n",
+            output_contents
+        );
+
+        remove_file(Path::new(expected_output_file)).unwrap();
+        remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
+    }
+
+    #[test]
+    fn test_get_late_days_with_extension() {
         let _ = env_logger::try_init();
         let user_json = fs::read_to_string("test/resources/exampleuser.json")
             .unwrap_or_else(|_| panic!("Unable to read user data"));
@@ -709,22 +3832,23 @@ mod tests {
             .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
         let starter_commit_hash = String::from("79ca81e76a65ff5009596c6e60b99ad0");
-        let due_date = NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
+        let due_date = NaiveDateTime::parse_from_str("2023-01-25 14:30", DATE_TIME_FORMAT).unwrap();
         let due_date = due_date.and_local_timezone(Eastern).unwrap();
-        let default_tolerance = Duration::from_secs(900);
+        let section_due_date = due_date + chrono::Duration::hours(4);
 
         let config = GitLabConfig {
-            designation: "a2".to_string(),
-            starter_commit_hash,
-            group_name: "ece459".to_string(),
+            designation: "a4".to_string(),
             due_date_time: due_date,
-            tolerance: default_tolerance,
+            extensions: parse_extensions_file(
+                &String::from("test/resources/extensions.csv"),
+                Eastern,
+            )
+            .unwrap(),
+            sections: parse_sections_file(&String::from("test/resources/sections.csv")).unwrap(),
+            section_due_dates: HashMap::from([(String::from("001"), section_due_date)]),
+            ..test_config("ece459", &starter_commit_hash, TimestampSource::Commit)
         };
-        let mut repo_members = Vec::new();
-        let mut inner = Vec::new();
-        inner.push(String::from("username"));
-        inner.push(String::from("u2sernam"));
-        repo_members.push(inner);
+        let repos = vec![Repo::new(1, vec![String::from("username")])];
 
         let server = MockServer::start();
         let get_user_mock = server.mock(|when, then| {
@@ -735,7 +3859,7 @@ mod tests {
         });
         let get_proj_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/api/v4/projects/ece459%2Fece459-a2-g1");
+                .path("/api/v4/projects/ece459%2Fece459-a4-username");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(project_json);
@@ -743,7 +3867,7 @@ mod tests {
 
         let get_branch_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/api/v4/projects/4/repository/branches/main"));
+                .path("/api/v4/projects/4/repository/branches/main");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(branch_json);
@@ -752,20 +3876,34 @@ mod tests {
         let server_url = server.base_url();
         let server_url = server_url.strip_prefix("http://").unwrap();
         let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
-        get_late_days(gitlab, repo_members, config);
+        get_late_days(gitlab, repos, config).unwrap();
 
         // Check that the URL was actually called!
         get_user_mock.assert();
         get_proj_mock.assert();
         get_branch_mock.assert();
-        let expected_output_file = "ece459-a2-latedays.csv";
-        let expected_nochanges_file = "ece459-a2-nochange.csv";
+        let expected_output_file = "ece459-a4-latedays.csv";
+        let expected_nochanges_file = "ece459-a4-nochange.csv";
+        let expected_timestamps_file = "ece459-a4-timestamps.csv";
+        let expected_errors_file = "ece459-a4-errors.csv";
+        let expected_exceeded_file = "ece459-a4-exceeded.csv";
+        let expected_no_submission_file = "ece459-a4-nosubmission.csv";
         let output_contents = fs::read_to_string(expected_output_file)
             .unwrap_or_else(|_| panic!("Unable to read user data"));
-        assert_eq!("username,0\nu2sernam,0\n", output_contents);
+        assert_eq!("username,0\n", output_contents);
+        let timestamps_contents = fs::read_to_string(expected_timestamps_file)
+            .unwrap_or_else(|_| panic!("Unable to read timestamps data"));
+        assert_eq!(
+            "ece459-a4-username,7b5c3cc8be40ee161ae89a06bba6229da1032a0c,2023-01-27T03:44:20-05:00,commit,2023-01-27T18:45:00-05:00,username,main,001\n",
+            timestamps_contents
+        );
 
         remove_file(Path::new(expected_output_file)).unwrap();
         remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
     }
 
     #[test]
@@ -779,21 +3917,12 @@ mod tests {
             .unwrap_or_else(|_| panic!("Unable to read branch data"));
 
         let starter_commit_hash = String::from("7b5c3cc8be40ee161ae89a06bba6229da1032a0c");
-        let due_date = NaiveDateTime::parse_from_str("2023-01-27 14:30", DATE_TIME_FORMAT).unwrap();
-        let due_date = due_date.and_local_timezone(Eastern).unwrap();
-        let default_tolerance = Duration::from_secs(900);
 
         let config = GitLabConfig {
             designation: "a3".to_string(),
-            starter_commit_hash,
-            group_name: "ece459".to_string(),
-            due_date_time: due_date,
-            tolerance: default_tolerance,
+            ..test_config("ece459", &starter_commit_hash, TimestampSource::Commit)
         };
-        let mut repo_members = Vec::new();
-        let mut inner = Vec::new();
-        inner.push(String::from("username"));
-        repo_members.push(inner);
+        let repos = vec![Repo::new(1, vec![String::from("username")])];
 
         let server = MockServer::start();
         let get_user_mock = server.mock(|when, then| {
@@ -812,7 +3941,7 @@ mod tests {
 
         let get_branch_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/api/v4/projects/4/repository/branches/main"));
+                .path("/api/v4/projects/4/repository/branches/main");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(branch_json);
@@ -821,7 +3950,7 @@ mod tests {
         let server_url = server.base_url();
         let server_url = server_url.strip_prefix("http://").unwrap();
         let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
-        get_late_days(gitlab, repo_members, config);
+        get_late_days(gitlab, repos, config).unwrap();
 
         // Check that the URL was actually called!
         get_user_mock.assert();
@@ -829,11 +3958,19 @@ mod tests {
         get_branch_mock.assert();
         let expected_output_file = "ece459-a3-latedays.csv";
         let expected_nochanges_file = "ece459-a3-nochange.csv";
+        let expected_timestamps_file = "ece459-a3-timestamps.csv";
+        let expected_errors_file = "ece459-a3-errors.csv";
+        let expected_exceeded_file = "ece459-a3-exceeded.csv";
+        let expected_no_submission_file = "ece459-a3-nosubmission.csv";
         let nochanges_content = fs::read_to_string(expected_nochanges_file)
             .unwrap_or_else(|_| panic!("Unable to read user data"));
         assert_eq!("username\n", nochanges_content);
 
         remove_file(Path::new(expected_output_file)).unwrap();
         remove_file(Path::new(expected_nochanges_file)).unwrap();
+        remove_file(Path::new(expected_timestamps_file)).unwrap();
+        remove_file(Path::new(expected_errors_file)).unwrap();
+        remove_file(Path::new(expected_exceeded_file)).unwrap();
+        remove_file(Path::new(expected_no_submission_file)).unwrap();
     }
 }
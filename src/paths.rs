@@ -0,0 +1,108 @@
+use glob::{MatchOptions, Pattern};
+
+use crate::error::Error;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+// Which paths in a repo are graded. With no include globs everything is, apart from whatever
+// the exclude globs match. * stays within a directory and ** crosses them, as in .gitignore.
+#[derive(Debug, Default)]
+pub struct PathFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<PathFilter, Error> {
+        Ok(PathFilter {
+            include: parse_globs(include)?,
+            exclude: parse_globs(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn is_graded(&self, path: &str) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|glob| glob.matches_with(path, MATCH_OPTIONS));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|glob| glob.matches_with(path, MATCH_OPTIONS))
+    }
+}
+
+fn parse_globs(globs: &[String]) -> Result<Vec<Pattern>, Error> {
+    globs
+        .iter()
+        .map(|glob| {
+            Pattern::new(glob).map_err(|e| Error::BadArgs(format!("Invalid path glob {glob}: {e}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::paths::PathFilter;
+
+    fn globs(globs: &[&str]) -> Vec<String> {
+        globs.iter().map(|glob| glob.to_string()).collect()
+    }
+
+    #[test]
+    fn everything_is_graded_by_default() {
+        let filter = PathFilter::new(&[], &[]).unwrap();
+
+        assert!(filter.is_empty());
+        assert!(filter.is_graded("README.md"));
+        assert!(filter.is_graded("src/main.rs"));
+    }
+
+    #[test]
+    fn exclude_leaves_out_matching_paths() {
+        let filter = PathFilter::new(&[], &globs(&["README.md", ".gitignore", "**/*.md"])).unwrap();
+
+        assert!(!filter.is_graded("README.md"));
+        assert!(!filter.is_graded(".gitignore"));
+        assert!(!filter.is_graded("docs/notes.md"));
+        assert!(filter.is_graded("src/main.rs"));
+        assert!(filter.is_graded("src/.gitignore"));
+    }
+
+    #[test]
+    fn include_limits_to_matching_paths() {
+        let filter =
+            PathFilter::new(&globs(&["src/**", "Cargo.toml"]), &globs(&["src/**/*.md"])).unwrap();
+
+        assert!(filter.is_graded("src/main.rs"));
+        assert!(filter.is_graded("src/bin/client.rs"));
+        assert!(filter.is_graded("Cargo.toml"));
+        assert!(!filter.is_graded("README.md"));
+        assert!(!filter.is_graded("src/notes.md"));
+    }
+
+    #[test]
+    fn single_star_stays_in_one_directory() {
+        let filter = PathFilter::new(&globs(&["src/*.rs"]), &[]).unwrap();
+
+        assert!(filter.is_graded("src/main.rs"));
+        assert!(!filter.is_graded("src/bin/client.rs"));
+    }
+
+    #[test]
+    fn invalid_globs_are_rejected() {
+        let error = PathFilter::new(&globs(&["src/["]), &[]).unwrap_err();
+
+        assert!(error.to_string().starts_with("Invalid path glob src/["));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use gitlab::api::projects::repository::branches::BranchBuilder;
+use gitlab::api::projects::repository::commits::CommitBuilder;
+use gitlab::api::{projects, Query};
+use gitlab::Gitlab;
+
+use crate::error::Error;
+use crate::{
+    for_each_project, get_project_names, Branch, Commit, GitLabConfig, Project, Repo,
+    DEFAULT_BRANCH_NAME,
+};
+
+// What a dry run found for one project. Problems would make the real run go wrong; notes are
+// worth a look but don't stop anything.
+#[derive(Debug, Default)]
+struct ProjectCheck {
+    problems: Vec<Error>,
+    notes: Vec<String>,
+}
+
+// Resolves every repo the way a real run would, without writing anything. Returns the problems
+// found, in roster order.
+pub fn dry_run(client: &Gitlab, config: &GitLabConfig, repos: &[Repo]) -> Vec<Error> {
+    let project_names = get_project_names(config, repos);
+    let checks = for_each_project(config.jobs, &project_names, |project_name| {
+        check_project(client, config, project_name)
+    });
+
+    let mut problems = Vec::new();
+    for (project_name, check) in project_names.iter().zip(checks) {
+        if check.problems.is_empty() {
+            println!("Project {project_name} is ready.");
+        }
+        for problem in &check.problems {
+            println!("Project {project_name}: {problem}.");
+        }
+        for note in &check.notes {
+            println!("Project {project_name}: {note}.");
+        }
+        problems.extend(check.problems);
+    }
+    for (student, labels) in find_duplicate_students(repos) {
+        let problem = Error::Roster(format!(
+            "Student {student} is on more than one roster line: {}",
+            labels.join(", ")
+        ));
+        println!("{problem}.");
+        problems.push(problem);
+    }
+    problems
+}
+
+fn check_project(client: &Gitlab, config: &GitLabConfig, project_name: &String) -> ProjectCheck {
+    let mut check = ProjectCheck::default();
+    let group_name = &config.group_name;
+    let project_builder = projects::ProjectBuilder::default()
+        .project(format!("{group_name}/{project_name}"))
+        .build()
+        .unwrap();
+    let project: Project = match project_builder.query(client) {
+        Ok(project) => project,
+        Err(e) => {
+            check
+                .problems
+                .push(Error::lookup(e, "project", Error::ProjectNotFound));
+            return check;
+        }
+    };
+
+    for starter_commit in &config.starter_commits {
+        let commit_builder = CommitBuilder::default()
+            .project(project.id)
+            .commit(starter_commit.as_str())
+            .build()
+            .unwrap();
+        let commit: Result<Commit, _> = commit_builder.query(client);
+        if let Err(e) = commit {
+            let what = format!("starter commit {starter_commit}");
+            check.problems.push(Error::lookup(e, &what, |reason| {
+                Error::GitLab(format!("{what} not found: {reason}"))
+            }));
+        }
+    }
+
+    // Tags and all-branches mode don't depend on any one branch being there.
+    if config.tag.is_some() || config.all_branches {
+        return check;
+    }
+    let default_branch = project
+        .default_branch
+        .unwrap_or_else(|| String::from(DEFAULT_BRANCH_NAME));
+    let branch_name = match &config.branch {
+        Some(branch_name) => {
+            if branch_name != &default_branch {
+                check.notes.push(format!(
+                    "default branch is {default_branch}; {branch_name} will be evaluated"
+                ));
+            }
+            branch_name
+        }
+        None => {
+            if default_branch != DEFAULT_BRANCH_NAME {
+                check.notes.push(format!(
+                    "default branch is {default_branch}, not {DEFAULT_BRANCH_NAME}"
+                ));
+            }
+            &default_branch
+        }
+    };
+    let branch_builder = BranchBuilder::default()
+        .project(project.id)
+        .branch(branch_name.as_str())
+        .build()
+        .unwrap();
+    let branch: Result<Branch, _> = branch_builder.query(client);
+    if let Err(e) = branch {
+        check.problems.push(Error::lookup(
+            e,
+            &format!("branch {branch_name}"),
+            |reason| Error::BranchNotFound {
+                branch: branch_name.clone(),
+                reason,
+            },
+        ));
+    }
+    check
+}
+
+// Students on more than one roster line, with the repos they'd be charged for.
+fn find_duplicate_students(repos: &[Repo]) -> BTreeMap<&String, Vec<String>> {
+    let mut labels: BTreeMap<&String, Vec<String>> = BTreeMap::new();
+    for repo in repos {
+        for student in &repo.members {
+            labels.entry(student).or_default().push(repo.label.clone());
+        }
+    }
+    labels.retain(|_, labels| labels.len() > 1);
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use gitlab::Gitlab;
+    use httpmock::prelude::*;
+
+    use crate::error::Error;
+    use crate::plan::{check_project, dry_run, find_duplicate_students};
+    use crate::roster::load_roster;
+    use crate::tests::test_config;
+    use crate::TimestampSource;
+
+    #[test]
+    fn duplicate_students_are_found() {
+        let mut repos =
+            load_roster(&String::from("test/resources/mixed.csv"), false, true).unwrap();
+        repos.push(repos[0].clone());
+
+        let duplicates = find_duplicate_students(&repos);
+
+        assert_eq!(repos[0].members.len(), duplicates.len());
+        for labels in duplicates.values() {
+            assert_eq!(
+                &vec![repos[0].label.clone(), repos[0].label.clone()],
+                labels
+            );
+        }
+    }
+
+    #[test]
+    fn check_project_reports_what_is_wrong() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+        let project_json = fs::read_to_string("test/resources/exampleproject.json")
+            .unwrap_or_else(|_| panic!("Unable to read project data"));
+        let branch_json = fs::read_to_string("test/resources/examplebranch.json")
+            .unwrap_or_else(|_| panic!("Unable to read branch data"));
+        let commits_json = fs::read_to_string("test/resources/examplecommits.json")
+            .unwrap_or_else(|_| panic!("Unable to read commits data"));
+        let starter_json =
+            serde_json::from_str::<serde_json::Value>(&commits_json).unwrap()[3].to_string();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a1-username");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(project_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/ece459%2Fece459-a1-u2sernam");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
+        let get_starter_mock = server.mock(|when, then| {
+            when.method(GET).path(
+                "/api/v4/projects/4/repository/commits/4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            );
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(starter_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(
+                "/api/v4/projects/4/repository/commits/9f3b2a1c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f80",
+            );
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Commit Not Found"}"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/main");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(branch_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/4/repository/branches/master");
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Branch Not Found"}"#);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Commit,
+        );
+
+        let ready = check_project(&gitlab, &config, &String::from("ece459-a1-username"));
+        let missing = check_project(&gitlab, &config, &String::from("ece459-a1-u2sernam"));
+        config
+            .starter_commits
+            .push(String::from("9f3b2a1c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f80"));
+        config.branch = Some(String::from("master"));
+        let wrong = check_project(&gitlab, &config, &String::from("ece459-a1-username"));
+
+        get_starter_mock.assert_hits(2);
+        assert!(ready.problems.is_empty());
+        assert!(ready.notes.is_empty());
+        assert_eq!(1, missing.problems.len());
+        assert!(matches!(missing.problems[0], Error::ProjectNotFound(_)));
+        assert!(missing.problems[0]
+            .to_string()
+            .starts_with("project not found"));
+        assert_eq!(2, wrong.problems.len());
+        assert!(wrong.problems[0]
+            .to_string()
+            .starts_with("starter commit 9f3b2a1c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f80 not found"));
+        assert!(wrong.problems[1]
+            .to_string()
+            .starts_with("branch master not found"));
+        assert_eq!(6, wrong.problems[1].exit_code());
+        assert_eq!(
+            vec!["default branch is main; master will be evaluated"],
+            wrong.notes
+        );
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let _ = env_logger::try_init();
+        let user_json = fs::read_to_string("test/resources/exampleuser.json")
+            .unwrap_or_else(|_| panic!("Unable to read user data"));
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v4/user");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(user_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET);
+            then.status(404)
+                .header("content-type", "application/json")
+                .body(r#"{"message": "404 Project Not Found"}"#);
+        });
+
+        let server_url = server.base_url();
+        let server_url = server_url.strip_prefix("http://").unwrap();
+        let gitlab = Gitlab::new_insecure(server_url, "00").unwrap();
+        let mut config = test_config(
+            "ece459",
+            "4ad91d3c1144c406e50c7b33bae684bd6837faf8",
+            TimestampSource::Commit,
+        );
+        config.designation = String::from("a8");
+        let repos = load_roster(&String::from("test/resources/simple.csv"), false, true).unwrap();
+        let mut duplicated = repos.clone();
+        duplicated.push(repos[0].clone());
+
+        let problems = dry_run(&gitlab, &config, &duplicated);
+
+        assert_eq!(duplicated.len() + 1, problems.len());
+        assert_eq!(5, problems[0].exit_code());
+        assert_eq!(3, problems[duplicated.len()].exit_code());
+        assert!(!Path::new("ece459-a8-latedays.csv").exists());
+        assert!(!Path::new("ece459-a8-nochange.csv").exists());
+    }
+}
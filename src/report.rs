@@ -0,0 +1,193 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::TimestampSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// One JSON array with an object per project
+    Json,
+    /// One JSON object per project, a line each
+    Jsonl,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Late,
+    OnTime,
+    // Later than the assessment's maximum; not charged.
+    Exceeded,
+    NoChange,
+    // Tag mode only: no tag matched.
+    NoSubmission,
+    Error,
+}
+
+// Everything known about one project, for when a student disputes their number. Fields that
+// don't apply (no commit to speak of, or the project couldn't be read at all) are null.
+#[derive(Debug, Serialize)]
+pub struct ReportEntry {
+    pub project: String,
+    pub project_id: Option<u64>,
+    pub members: Vec<String>,
+    pub status: Status,
+    pub branch: Option<String>,
+    pub commit: Option<String>,
+    pub timestamp_utc: Option<String>,
+    pub timestamp_local: Option<String>,
+    pub timestamp_source: Option<TimestampSource>,
+    pub effective_due: Option<String>,
+    pub extension: Option<String>,
+    pub section: Option<String>,
+    pub minutes_late: Option<i64>,
+    // In whatever unit the lateness policy charges in; days unless configured otherwise.
+    pub late_days: Option<f64>,
+    pub ignored_commits: Vec<IgnoredCommit>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+
+// A commit after the one used as the submission that didn't count, and why.
+#[derive(Debug, Serialize)]
+pub struct IgnoredCommit {
+    pub commit: String,
+    pub timestamp: String,
+    pub reason: String,
+}
+
+impl ReportEntry {
+    pub fn new(project: String, members: Vec<String>, status: Status) -> ReportEntry {
+        ReportEntry {
+            project,
+            project_id: None,
+            members,
+            status,
+            branch: None,
+            commit: None,
+            timestamp_utc: None,
+            timestamp_local: None,
+            timestamp_source: None,
+            effective_due: None,
+            extension: None,
+            section: None,
+            minutes_late: None,
+            late_days: None,
+            ignored_commits: Vec::new(),
+            warning: None,
+            error: None,
+        }
+    }
+}
+
+pub fn write_report(
+    filename: &str,
+    format: ReportFormat,
+    entries: &[ReportEntry],
+) -> Result<(), Error> {
+    let file =
+        File::create(filename).map_err(|e| Error::io(format!("Unable to create {filename}"), e))?;
+    let write_error = |e: io::Error| Error::io(format!("Unable to write {filename}"), e);
+    let mut writer = BufWriter::new(file);
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, entries)
+                .map_err(|e| write_error(e.into()))?;
+            writeln!(writer).map_err(write_error)?;
+        }
+        ReportFormat::Jsonl => {
+            for entry in entries {
+                serde_json::to_writer(&mut writer, entry).map_err(|e| write_error(e.into()))?;
+                writeln!(writer).map_err(write_error)?;
+            }
+        }
+    }
+    writer.flush().map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::remove_file;
+
+    use serde_json::Value;
+
+    use crate::report::{write_report, ReportEntry, ReportFormat, Status};
+    use crate::TimestampSource;
+
+    fn entries() -> Vec<ReportEntry> {
+        let mut late = ReportEntry::new(
+            String::from("ece459/ece459-a1-g1"),
+            vec![String::from("username"), String::from("u2sernam")],
+            Status::Late,
+        );
+        late.project_id = Some(4);
+        late.timestamp_source = Some(TimestampSource::Push);
+        late.minutes_late = Some(90);
+        late.late_days = Some(1.0);
+        let no_change = ReportEntry::new(
+            String::from("ece459/ece459-a1-u3sernam"),
+            vec![String::from("u3sernam")],
+            Status::NoChange,
+        );
+        vec![late, no_change]
+    }
+
+    #[test]
+    fn json_report_is_one_array() {
+        let filename = "report-test.json";
+
+        write_report(filename, ReportFormat::Json, &entries()).unwrap();
+
+        let contents = fs::read_to_string(filename).unwrap();
+        remove_file(filename).unwrap();
+        let report: Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(2, report.as_array().unwrap().len());
+        assert_eq!("late", report[0]["status"]);
+        assert_eq!("push", report[0]["timestamp_source"]);
+        assert_eq!(90, report[0]["minutes_late"]);
+        assert_eq!("no-change", report[1]["status"]);
+        assert!(report[1]["commit"].is_null());
+    }
+
+    #[test]
+    fn jsonl_report_is_one_object_per_line() {
+        let filename = "report-test.jsonl";
+
+        write_report(filename, ReportFormat::Jsonl, &entries()).unwrap();
+
+        let contents = fs::read_to_string(filename).unwrap();
+        remove_file(filename).unwrap();
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, lines.len());
+        assert_eq!("ece459/ece459-a1-g1", lines[0]["project"]);
+        assert_eq!("u3sernam", lines[1]["members"][0]);
+    }
+
+    // /dev/full takes the file being opened, then fails every write, like a full disk.
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn full_disk_is_an_io_error() {
+        let error = write_report("/dev/full", ReportFormat::Jsonl, &entries()).unwrap_err();
+
+        assert_eq!(7, error.exit_code());
+        assert!(error.to_string().starts_with("Unable to write /dev/full"));
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::Error;
+use crate::{parse_csv_file, Repo};
+
+// One repo's worth of the roster. A group spread over several rows (a header with a group
+// column) is on the line of its first row.
+#[derive(Debug, Clone, PartialEq)]
+struct RosterEntry {
+    line: usize,
+    // The N in g<N>: the line number, counted from the line after the header if there is one.
+    number: usize,
+    // Given outright, instead of by the line rule.
+    label: Option<String>,
+    // Set when the rows were gathered by a group column, which the label then comes from.
+    group: Option<String>,
+    members: Vec<String>,
+}
+
+impl RosterEntry {
+    // What the line rule makes of this entry, whatever its label says.
+    fn line_label(&self) -> String {
+        let members = self
+            .members
+            .iter()
+            .filter(|member| !member.is_empty())
+            .cloned()
+            .collect();
+        Repo::new(self.number, members).label
+    }
+}
+
+// Reads and checks the roster before anything talks to GitLab. Problems are reported; in strict
+// mode they stop the run, otherwise what can be fixed is.
+pub fn load_roster(filename: &String, header: bool, strict: bool) -> Result<Vec<Repo>, Error> {
+    let entries = if header {
+        read_roster_with_header(filename)?
+    } else {
+        read_roster(filename)?
+    };
+    for warning in check_labels(filename, &entries) {
+        eprintln!("{warning}.");
+    }
+    let problems = validate_roster(filename, &entries);
+    for problem in &problems {
+        eprintln!("{problem}.");
+    }
+    if strict && !problems.is_empty() {
+        return Err(Error::Roster(format!(
+            "{} problem(s) in {filename}; fix them or leave out --strict",
+            problems.len()
+        )));
+    }
+    Ok(label_entries(clean_roster(entries)))
+}
+
+// The create-project-repos format: one repo per line, and nothing but usernames. A g<N> in front
+// of the usernames names the repo outright, so lines can be taken out without the groups after
+// them landing on the wrong repos.
+fn read_roster(filename: &String) -> Result<Vec<RosterEntry>, Error> {
+    Ok(parse_csv_file(filename)?
+        .into_iter()
+        .enumerate()
+        // Comments have no fields at all.
+        .filter(|(_, members)| !members.is_empty())
+        .map(|(i, mut members)| {
+            let label = if members.len() > 1 && is_group_label(&members[0]) {
+                Some(members.remove(0))
+            } else {
+                None
+            };
+            RosterEntry {
+                line: i + 1,
+                number: i + 1,
+                label,
+                group: None,
+                members,
+            }
+        })
+        .collect())
+}
+
+fn is_group_label(field: &str) -> bool {
+    field
+        .strip_prefix('g')
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+// A roster with a header row, like an export from the LMS. Columns are picked by name: username
+// (as many as a group needs), and optionally group, for one student per row, and repo (or label)
+// for the label. Anything else is ignored.
+fn read_roster_with_header(filename: &String) -> Result<Vec<RosterEntry>, Error> {
+    let rows = parse_csv_file(filename)?;
+    let Some(header_index) = rows
+        .iter()
+        .position(|row| row.iter().any(|field| !field.is_empty()))
+    else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = rows[header_index]
+        .iter()
+        .map(|name| name.to_lowercase())
+        .collect();
+    let columns = |name: &str| -> Vec<usize> {
+        header
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| *column == name)
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let username_columns = columns("username");
+    if username_columns.is_empty() {
+        return Err(Error::Roster(format!(
+            "The header row of {filename} has no username column"
+        )));
+    }
+    let group_column = columns("group").first().copied();
+    let repo_column = columns("repo")
+        .first()
+        .or(columns("label").first())
+        .copied();
+
+    let mut entries: Vec<RosterEntry> = Vec::new();
+    let mut entries_by_group: HashMap<String, usize> = HashMap::new();
+    for (i, row) in rows.iter().enumerate().skip(header_index + 1) {
+        if row.is_empty() {
+            continue;
+        }
+        let field = |column: usize| row.get(column).filter(|field| !field.is_empty()).cloned();
+        // Rows are as wide as the biggest group, so smaller groups leave usernames empty.
+        let members: Vec<String> = username_columns.iter().filter_map(|&c| field(c)).collect();
+        let label = repo_column.and_then(field);
+        let Some(group) = group_column.and_then(field) else {
+            entries.push(RosterEntry {
+                line: i + 1,
+                number: i - header_index,
+                label,
+                group: None,
+                members,
+            });
+            continue;
+        };
+        match entries_by_group.get(&group) {
+            Some(&index) => {
+                let entry = &mut entries[index];
+                entry.members.extend(members);
+                entry.label = entry.label.take().or(label);
+            }
+            None => {
+                entries_by_group.insert(group.clone(), entries.len());
+                entries.push(RosterEntry {
+                    line: i + 1,
+                    number: i - header_index,
+                    label: label.or_else(|| Some(group_label(&group))),
+                    group: Some(group),
+                    members,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// Group 7 is repo g7; a group that isn't just a number, such as g7, is the label as it is.
+fn group_label(group: &str) -> String {
+    match group.parse::<usize>() {
+        Ok(number) => format!("g{number}"),
+        Err(_) => String::from(group),
+    }
+}
+
+// A label that the line rule wouldn't give usually means lines have been added or taken out since
+// the repos were made. The label is what's used; this is only so someone looks.
+fn check_labels(filename: &str, entries: &[RosterEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| entry.group.is_none())
+        .filter_map(|entry| {
+            let label = entry.label.as_ref()?;
+            let line_label = entry.line_label();
+            (*label != line_label).then(|| {
+                format!(
+                    "Line {} of {filename} is labelled {label}, but by its line number it would be {line_label}; going by the label",
+                    entry.line
+                )
+            })
+        })
+        .collect()
+}
+
+fn validate_roster(filename: &str, entries: &[RosterEntry]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut lines_by_student: BTreeMap<&String, Vec<usize>> = BTreeMap::new();
+    let mut lines_by_repo: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+    for entry in entries {
+        let line = entry.line;
+        let members = &entry.members;
+        if members.iter().all(|member| member.is_empty()) {
+            problems.push(format!("Line {line} of {filename} is blank"));
+            continue;
+        }
+        if members.iter().any(|member| member.is_empty()) {
+            problems.push(format!("Line {line} of {filename} has an empty username"));
+        }
+        let repo = entry.label.clone().unwrap_or_else(|| entry.line_label());
+        lines_by_repo.entry(repo).or_default().push(line);
+        let mut seen: Vec<&String> = Vec::new();
+        let mut repeated: Vec<&String> = Vec::new();
+        for member in members.iter().filter(|member| !member.is_empty()) {
+            if !seen.contains(&member) {
+                seen.push(member);
+                lines_by_student.entry(member).or_default().push(line);
+            } else if !repeated.contains(&member) {
+                repeated.push(member);
+                problems.push(format!(
+                    "Line {line} of {filename} lists {member} more than once"
+                ));
+            }
+        }
+    }
+
+    for (student, lines) in lines_by_student {
+        if lines.len() > 1 {
+            let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            problems.push(format!(
+                "{student} is on more than one line of {filename} ({}) and would be charged for each",
+                lines.join(", ")
+            ));
+        }
+    }
+    // Without labels, two lines can only be the same repo by being the same student, which is
+    // already reported above.
+    let labelled: Vec<&String> = entries.iter().filter_map(|e| e.label.as_ref()).collect();
+    for (repo, lines) in lines_by_repo {
+        if lines.len() > 1 && labelled.contains(&&repo) {
+            let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            problems.push(format!(
+                "Repo {repo} is on more than one line of {filename} ({})",
+                lines.join(", ")
+            ));
+        }
+    }
+    problems
+}
+
+// Drops empty usernames and repeats within an entry. Entries left with nobody are kept, so they
+// can be skipped when labelling.
+fn clean_roster(entries: Vec<RosterEntry>) -> Vec<RosterEntry> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let mut cleaned: Vec<String> = Vec::new();
+            for member in entry.members {
+                if !member.is_empty() && !cleaned.contains(&member) {
+                    cleaned.push(member);
+                }
+            }
+            RosterEntry {
+                members: cleaned,
+                ..entry
+            }
+        })
+        .collect()
+}
+
+fn label_entries(entries: Vec<RosterEntry>) -> Vec<Repo> {
+    entries
+        .into_iter()
+        .filter(|entry| !entry.members.is_empty())
+        .map(|entry| match entry.label {
+            Some(label) => Repo {
+                label,
+                members: entry.members,
+            },
+            None => Repo::new(entry.number, entry.members),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::roster::{
+        check_labels, clean_roster, load_roster, read_roster, read_roster_with_header,
+        validate_roster,
+    };
+
+    #[test]
+    fn clean_roster_has_no_problems() {
+        let filename = "test/resources/mixed.csv";
+
+        let problems = validate_roster(filename, &read_roster(&String::from(filename)).unwrap());
+
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn problems_have_line_numbers() {
+        let filename = "test/resources/messy.csv";
+
+        let problems = validate_roster(filename, &read_roster(&String::from(filename)).unwrap());
+
+        assert_eq!(
+            vec![
+                "Line 1 of test/resources/messy.csv has an empty username",
+                "Line 3 of test/resources/messy.csv is blank",
+                "Line 4 of test/resources/messy.csv lists u4sernam more than once",
+                "Line 6 of test/resources/messy.csv is blank",
+                "u2sernam is on more than one line of test/resources/messy.csv (1, 5) and would be charged for each",
+            ],
+            problems
+        );
+    }
+
+    #[test]
+    fn cleaning_keeps_group_numbers() {
+        let entries = read_roster(&String::from("test/resources/messy.csv")).unwrap();
+
+        let cleaned = clean_roster(entries);
+
+        assert_eq!(6, cleaned.len());
+        assert_eq!(vec!["username", "u2sernam"], cleaned[0].members);
+        assert_eq!(vec!["u4sernam"], cleaned[3].members);
+        assert_eq!(4, cleaned[3].number);
+        assert!(cleaned[2].members.is_empty());
+        assert!(cleaned[5].members.is_empty());
+    }
+
+    #[test]
+    fn label_repos_by_line_number() {
+        let repos = load_roster(&String::from("test/resources/mixed.csv"), false, true).unwrap();
+
+        assert_eq!(2, repos.len());
+        assert_eq!("g1", repos[0].label);
+        assert_eq!(3, repos[0].members.len());
+        assert_eq!("u4sernam", repos[1].label);
+        assert_eq!(vec![String::from("u4sernam")], repos[1].members);
+    }
+
+    #[test]
+    fn messy_roster_is_fixed_up() {
+        let repos = load_roster(&String::from("test/resources/messy.csv"), false, false).unwrap();
+
+        let labels: Vec<&str> = repos.iter().map(|repo| repo.label.as_str()).collect();
+        assert_eq!(vec!["g1", "u3sernam", "u4sernam", "g5"], labels);
+    }
+
+    #[test]
+    fn strict_mode_refuses_a_messy_roster() {
+        let error =
+            load_roster(&String::from("test/resources/messy.csv"), false, true).unwrap_err();
+
+        assert!(matches!(error, Error::Roster(_)));
+        assert!(error
+            .to_string()
+            .starts_with("5 problem(s) in test/resources/messy.csv"));
+    }
+
+    #[test]
+    fn comments_are_not_blank_lines() {
+        let filename = "test/resources/excel.csv";
+
+        let repos = load_roster(&String::from(filename), false, false).unwrap();
+
+        let labels: Vec<&str> = repos.iter().map(|repo| repo.label.as_str()).collect();
+        assert_eq!(vec!["g2", "g4", "u5sernam"], labels);
+        assert_eq!(
+            vec!["Line 3 of test/resources/excel.csv is blank"],
+            validate_roster(filename, &read_roster(&String::from(filename)).unwrap())
+        );
+    }
+
+    #[test]
+    fn header_picks_columns_by_name() {
+        let repos = load_roster(&String::from("test/resources/header.csv"), true, true).unwrap();
+
+        let labels: Vec<&str> = repos.iter().map(|repo| repo.label.as_str()).collect();
+        assert_eq!(vec!["g1", "u3sernam", "g7"], labels);
+        assert_eq!(vec!["username", "u2sernam"], repos[0].members);
+        assert_eq!(vec!["u4sernam", "u5sernam"], repos[2].members);
+    }
+
+    #[test]
+    fn group_column_gathers_one_student_per_row() {
+        let repos = load_roster(&String::from("test/resources/lms.csv"), true, true).unwrap();
+
+        let labels: Vec<&str> = repos.iter().map(|repo| repo.label.as_str()).collect();
+        assert_eq!(vec!["g2", "g3", "u4sernam"], labels);
+        assert_eq!(vec!["username", "u2sernam"], repos[0].members);
+        assert_eq!(vec!["u3sernam"], repos[1].members);
+    }
+
+    #[test]
+    fn header_needs_a_username_column() {
+        let filename = "test/resources/header_no_username.csv";
+
+        let error = read_roster_with_header(&String::from(filename)).unwrap_err();
+
+        assert!(matches!(error, Error::Roster(_)));
+        assert_eq!(
+            "The header row of test/resources/header_no_username.csv has no username column",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn missing_roster_is_an_io_error() {
+        let error =
+            load_roster(&String::from("test/resources/missing.csv"), false, true).unwrap_err();
+
+        assert_eq!(7, error.exit_code());
+    }
+
+    #[test]
+    fn labels_override_line_numbers() {
+        let filename = "test/resources/labelled.csv";
+
+        let repos = load_roster(&String::from(filename), false, true).unwrap();
+
+        let labels: Vec<&str> = repos.iter().map(|repo| repo.label.as_str()).collect();
+        assert_eq!(vec!["g1", "g3", "u5sernam", "g5"], labels);
+        assert_eq!(vec!["u3sernam", "u4sernam"], repos[1].members);
+        assert_eq!(
+            vec!["Line 2 of test/resources/labelled.csv is labelled g3, but by its line number it would be g2; going by the label"],
+            check_labels(filename, &read_roster(&String::from(filename)).unwrap())
+        );
+    }
+
+    #[test]
+    fn header_repo_labels_are_checked_but_group_labels_are_not() {
+        let header = "test/resources/header.csv";
+        let lms = "test/resources/lms.csv";
+
+        let header_warnings = check_labels(
+            header,
+            &read_roster_with_header(&String::from(header)).unwrap(),
+        );
+        let lms_warnings = check_labels(lms, &read_roster_with_header(&String::from(lms)).unwrap());
+
+        assert_eq!(
+            vec!["Line 4 of test/resources/header.csv is labelled g7, but by its line number it would be g3; going by the label"],
+            header_warnings
+        );
+        assert!(lms_warnings.is_empty());
+    }
+
+    #[test]
+    fn a_label_can_clash_with_a_line_number() {
+        let filename = "test/resources/clash.csv";
+
+        let problems = validate_roster(filename, &read_roster(&String::from(filename)).unwrap());
+
+        assert_eq!(
+            vec!["Repo g2 is on more than one line of test/resources/clash.csv (1, 2)"],
+            problems
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::due_date::DueDate;
+use crate::error::Error;
+use crate::parse_csv_file;
+
+// Which section each student, or group (g<N>), is in. Blank lines and comments are skipped.
+pub fn parse_sections_file(filename: &String) -> Result<HashMap<String, String>, Error> {
+    let mut result = HashMap::new();
+    for (i, line) in parse_csv_file(filename)?.into_iter().enumerate() {
+        match line.as_slice() {
+            [] => continue,
+            [who] if who.is_empty() => continue,
+            [who, section] if !who.is_empty() && !section.is_empty() => {
+                result.insert(who.clone(), section.clone());
+            }
+            _ => {
+                return Err(Error::BadArgs(format!(
+                    "Line {} of {filename} should be <username or g<N>>,<section>",
+                    i + 1
+                )))
+            }
+        }
+    }
+    Ok(result)
+}
+
+// SECTION=DUE, with the due date written like --due.
+pub fn parse_section_due(section_due: &str) -> Result<(String, DueDate), String> {
+    let (section, due) = section_due
+        .split_once('=')
+        .ok_or(format!("{section_due} should be SECTION=DUE"))?;
+    Ok((String::from(section.trim()), due.trim().parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::sections::{parse_section_due, parse_sections_file};
+
+    #[test]
+    fn can_parse_sections_file() {
+        let sections = parse_sections_file(&String::from("test/resources/sections.csv")).unwrap();
+
+        assert_eq!(4, sections.len());
+        assert_eq!(Some(&String::from("001")), sections.get("username"));
+        assert_eq!(Some(&String::from("002")), sections.get("g3"));
+    }
+
+    #[test]
+    fn sections_file_needs_a_section_per_line() {
+        let error = parse_sections_file(&String::from("test/resources/group.csv")).unwrap_err();
+
+        assert!(matches!(error, Error::BadArgs(_)));
+        assert!(error
+            .to_string()
+            .starts_with("Line 1 of test/resources/group.csv should be"));
+    }
+
+    #[test]
+    fn section_due_is_section_equals_due_date() {
+        let (section, due) = parse_section_due("002=2023-01-26 23:59").unwrap();
+
+        assert_eq!("002", section);
+        assert_eq!("2023-01-26 23:59".parse(), Ok(due));
+        assert!(parse_section_due("002").is_err());
+        assert!(parse_section_due("002=tomorrow").is_err());
+    }
+}